@@ -0,0 +1,77 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tx_fee_maximizer::*;
+
+const POPULATION_SIZE: usize = 256;
+const NUM_GENERATION: usize = 5;
+
+fn solver() -> GeneticSolver {
+    GeneticSolver::new(SolverConfig {
+        population_size: POPULATION_SIZE,
+        selection_size: 8,
+        num_generation: NUM_GENERATION,
+        seed: Some(1),
+        ..SolverConfig::default()
+    })
+}
+
+/// Throughput in evaluated agents per second, for growing mempools.
+fn bench_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("genetic/requests");
+    group.throughput(Throughput::Elements(
+        (POPULATION_SIZE * NUM_GENERATION) as u64,
+    ));
+    for num_requests in [100, 1_000, 5_000] {
+        let fm = MempoolGenerator {
+            num_accounts: num_requests / 2,
+            num_requests,
+            ..MempoolGenerator::default()
+        }
+        .generate(7);
+        group.bench_with_input(BenchmarkId::from_parameter(num_requests), &fm, |b, fm| {
+            b.iter(|| fm.clone().solve_with(&solver()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Throughput with dependencies and multi-transaction requests competing for funds.
+fn bench_structure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("genetic/structure");
+    group.throughput(Throughput::Elements(
+        (POPULATION_SIZE * NUM_GENERATION) as u64,
+    ));
+    for (name, generator) in [
+        (
+            "chains",
+            MempoolGenerator {
+                chain_depth: 8,
+                ..MempoolGenerator::default()
+            },
+        ),
+        (
+            "competition",
+            MempoolGenerator {
+                max_transactions_per_request: 4,
+                competition: 3.0,
+                balance: BalanceDistribution::Pareto {
+                    min: Amount::from_units(1),
+                    shape: 1.5,
+                },
+                ..MempoolGenerator::default()
+            },
+        ),
+    ] {
+        let fm = generator.generate(7);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &fm, |b, fm| {
+            b.iter(|| fm.clone().solve_with(&solver()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_sizes, bench_structure
+}
+criterion_main!(benches);
@@ -0,0 +1,95 @@
+//! Print the fee captured by every built-in solver on synthetic mempools,
+//! relative to the best of them, to track regressions in solution quality.
+//!
+//! The exact solver is only run on the smallest mempools.
+use std::time::Instant;
+use tx_fee_maximizer::*;
+
+const SEEDS: u64 = 3;
+const EXACT_MAX_REQUESTS: usize = 8;
+
+/// Small enough for the greedy solver, which evaluates every remaining request at every step.
+fn generator() -> MempoolGenerator {
+    MempoolGenerator {
+        num_accounts: 100,
+        num_requests: 300,
+        ..MempoolGenerator::default()
+    }
+}
+
+fn main() {
+    let config = SolverConfig {
+        population_size: 1024,
+        selection_size: 16,
+        num_generation: 20,
+        seed: Some(1),
+        ..SolverConfig::default()
+    };
+    let instances = [
+        (
+            "tiny",
+            MempoolGenerator {
+                num_accounts: 4,
+                num_requests: EXACT_MAX_REQUESTS,
+                max_transactions_per_request: 2,
+                competition: 2.0,
+                ..MempoolGenerator::default()
+            },
+        ),
+        ("independent", generator()),
+        (
+            "chains",
+            MempoolGenerator {
+                chain_depth: 8,
+                ..generator()
+            },
+        ),
+        (
+            "competition",
+            MempoolGenerator {
+                max_transactions_per_request: 4,
+                competition: 3.0,
+                balance: BalanceDistribution::Pareto {
+                    min: Amount::from_units(1),
+                    shape: 1.5,
+                },
+                ..generator()
+            },
+        ),
+    ];
+
+    println!(
+        "{:<12} {:>4} {:<8} {:>20} {:>8} {:>10}",
+        "instance", "seed", "solver", "fee", "ratio", "time"
+    );
+    for (name, generator) in instances {
+        for seed in 0..SEEDS {
+            let fm = generator.generate(seed);
+            let results = SOLVER_NAMES
+                .iter()
+                .filter(|solver| {
+                    **solver != "exact" || generator.num_requests <= EXACT_MAX_REQUESTS
+                })
+                .map(|solver| {
+                    let start = Instant::now();
+                    let solution = fm
+                        .clone()
+                        .solve_with(solver_from_name(solver, config.clone()).unwrap().as_ref())
+                        .unwrap();
+                    (*solver, solution.total_fee, start.elapsed())
+                })
+                .collect::<Vec<_>>();
+            let best = results.iter().map(|(_, fee, _)| *fee).max().unwrap();
+            for (solver, fee, elapsed) in results {
+                let ratio = match best.minor() {
+                    0 => 1.0,
+                    best => fee.minor() as f64 / best as f64,
+                };
+                println!(
+                    "{name:<12} {seed:>4} {solver:<8} {:>20} {ratio:>8.4} {elapsed:>10.2?}",
+                    fee.to_string()
+                );
+            }
+        }
+    }
+}
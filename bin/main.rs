@@ -1,8 +1,8 @@
-use clap::Parser;
-use csv::Reader;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::Path;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fs::File;
+use std::io::{stdin, stdout, BufReader, Read, Write};
+use std::process::exit;
+use std::time::Duration;
 use tx_fee_maximizer::*;
 
 #[derive(Parser, Debug)]
@@ -11,20 +11,87 @@ use tx_fee_maximizer::*;
     about = "Evolution Algorithm",
     long_about = "See Readme for Detailed Input Format"
 )]
-struct Args {
-    /// path to balance csv, must include 2 columns: User,Balance,
-    /// with the exact headers.
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    solve: SolveArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check a JSON solution against the balance and requests, replaying its transactions in order
+    Verify(VerifyArgs),
+    /// Convert the balance and requests files between csv and JSON
+    Convert(ConvertArgs),
+}
+
+/// the balance and requests files.
+#[derive(Args, Debug)]
+struct Inputs {
+    /// path to balance file, or `-` to read it from stdin.
+    ///
+    /// A csv must include 2 columns: User,Balance, with the exact headers,
+    /// and optionally the column Asset. The data types are string,decimal(,string).
+    ///
+    /// A JSON file maps every address to an amount in the native asset,
+    /// or to a map from asset to amount.
+    #[arg(short, long, alias = "balance-csv", required = true)]
+    balance: Option<String>,
+
+    /// path to requests file, or `-` to read it from stdin.
+    ///
+    /// A csv must include 5 columns: request,from,to,amount,fee
+    /// with the exact headers, and optionally the columns atomic, weight, asset and fee_asset.
+    /// The data types are int,string,string,decimal,decimal(,bool,int,string,string)
     ///
-    /// The data types are string,float.
-    #[arg(short, long)]
-    balance_csv: String,
+    /// A JSON file is an array of requests with the fields transactions, and optionally id and atomic.
+    #[arg(short, long, required = true)]
+    requests: Option<String>,
+
+    /// format of the balance and requests files
+    #[arg(long, value_enum, default_value_t = InputFormat::Csv)]
+    input_format: InputFormat,
+}
 
-    /// path to requests csv, must include 5 columns: request,from,to,amount,fee
-    /// with the exact headers.
+impl Inputs {
+    /// Paths of the balance and requests files.
     ///
-    /// The data types are int,string,string,float,float
-    #[arg(short, long)]
-    requests: String,
+    /// They are required, and only missing when a subcommand is given instead of solving.
+    fn paths(&self) -> (&str, &str) {
+        match (&self.balance, &self.requests) {
+            (Some(balance), Some(requests)) => (balance, requests),
+            _ => unreachable!("clap requires the balance and requests"),
+        }
+    }
+}
+
+/// the block and objective, shared by solving and verifying.
+#[derive(Args, Debug)]
+struct Block {
+    /// maximum number of transactions in the block
+    #[arg(long)]
+    max_transactions: Option<usize>,
+
+    /// maximum total weight of the transactions in the block
+    #[arg(long)]
+    max_weight: Option<u64>,
+
+    /// asset whose fees are maximized
+    #[arg(long, default_value = NATIVE_ASSET_STR)]
+    objective_asset: String,
+}
+
+#[derive(Args, Debug)]
+struct SolveArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// format of the solution printed to stdout
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
 
     /// population size (solver parameter)
     #[arg(short, long, default_value_t = 8192)]
@@ -37,80 +104,388 @@ struct Args {
     /// number of generation (solver parameter)
     #[arg(short, long, default_value_t = 50)]
     num_generation: usize,
+
+    /// rng seed for reproducible results (solver parameter)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// maximum solving time in milliseconds, returning the best solution found so far (solver parameter)
+    #[arg(long)]
+    time_budget_ms: Option<u64>,
+
+    /// stop after this many generations without improvement of the best fee (solver parameter)
+    #[arg(long)]
+    patience: Option<usize>,
+
+    /// selection curve: linear, sigmoid[:<steepness>] or power[:<exponent>] (solver parameter)
+    #[arg(long, default_value = "linear")]
+    selection_curve: SelectionCurve,
+
+    /// minimum probability of selecting, and of dropping, any transaction, up to 0.5 (solver parameter)
+    #[arg(long, default_value_t = 0.0)]
+    mutation_rate: f64,
+
+    /// probability that an agent selects transactions ignoring the learned weights (solver parameter)
+    #[arg(long, default_value_t = 0.0)]
+    selection_exploration: f64,
+
+    /// probability that an agent orders requests ignoring the learned weights (solver parameter)
+    #[arg(long, default_value_t = 0.0)]
+    order_exploration: f64,
+
+    /// solver to use: genetic, greedy or exact
+    #[arg(long, default_value = "genetic")]
+    solver: String,
+
+    /// check that the solution conserves every asset, leaves no negative balance
+    /// and pays the system exactly the fees (always on in debug builds)
+    #[arg(long)]
+    audit: bool,
+
+    /// tell why every rejected transaction is not executed, in the text report only
+    #[arg(long)]
+    explain: bool,
+
+    #[command(flatten)]
+    block: Block,
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// path to the JSON solution, as printed with `--output-format json`, or `-` to read it from stdin.
+    #[arg(long)]
+    solution: String,
+
+    #[command(flatten)]
+    block: Block,
+}
+
+#[derive(Args, Debug)]
+struct ConvertArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// format of the converted files
+    #[arg(long, value_enum)]
+    to: InputFormat,
+
+    /// path of the converted balance file, or `-` to write it to stdout.
+    #[arg(long)]
+    balance_out: String,
+
+    /// path of the converted requests file, or `-` to write it to stdout.
+    #[arg(long)]
+    requests_out: String,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum InputFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// human-readable report
+    Text,
+    /// the solution as a JSON object
+    Json,
+}
+
+/// Open a file, or stdin if the path is `-`.
+fn open(path: &str) -> Result<Box<dyn Read>, FeeMaximizerError> {
+    if path == "-" {
+        Ok(Box::new(stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Create a file, or write to stdout if the path is `-`.
+fn create(path: &str) -> Result<Box<dyn Write>, FeeMaximizerError> {
+    if path == "-" {
+        Ok(Box::new(stdout().lock()))
+    } else {
+        Ok(Box::new(File::create(path)?))
+    }
+}
+
+fn load(fm: &mut FeeMaximizer, inputs: &Inputs) -> Result<(), FeeMaximizerError> {
+    let (balance, requests) = inputs.paths();
+    match inputs.input_format {
+        InputFormat::Csv => {
+            fm.add_balance_from_reader(open(balance)?, true)?;
+            fm.add_requests_from_reader(open(requests)?, true)
+        }
+        InputFormat::Json => {
+            fm.add_balance_from_json(open(balance)?)?;
+            fm.add_requests_from_json(open(requests)?)
+        }
+    }
+}
+
+/// Set the objective asset and the block capacity.
+fn configure(fm: &mut FeeMaximizer, block: &Block) -> Result<(), FeeMaximizerError> {
+    fm.set_objective_asset(Asset::from_string(block.objective_asset.clone())?);
+    fm.set_block_capacity(BlockCapacity {
+        max_transactions: block.max_transactions,
+        max_weight: block.max_weight,
+    });
+    Ok(())
 }
 
 fn main() {
-    let arg: Args = Args::parse();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Verify(arg)) => verify(arg),
+        Some(Command::Convert(arg)) => convert(arg),
+        None => solve(cli.solve),
+    }
+}
 
-    let mut fm = FeeMaximizer::init_empty();
-    if let Err(e) = fm.add_balance_from_csv(&arg.balance_csv, true) {
-        eprintln!("Error: {}", e);
-        return;
+/// Solve and print the solution, or the error and exit with status 1.
+fn solve(arg: SolveArgs) {
+    if arg.inputs.paths() == ("-", "-") {
+        eprintln!("Error: balance and requests cannot both be read from stdin");
+        exit(1);
+    }
+    if arg.explain && matches!(arg.output_format, OutputFormat::Json) {
+        eprintln!("Error: --explain cannot be combined with --output-format json");
+        exit(1);
     }
 
-    if let Err(e) = write_requests(&mut fm, arg.requests) {
+    let mut fm = FeeMaximizer::init_empty();
+    if let Err(e) = load(&mut fm, &arg.inputs).and_then(|_| configure(&mut fm, &arg.block)) {
         eprintln!("Error: {}", e);
-        return;
+        exit(1);
+    }
+    if arg.audit {
+        fm.set_audit(true);
     }
 
-    println!("Start solving...");
+    let solver = match solver_from_name(
+        &arg.solver,
+        SolverConfig {
+            population_size: arg.population_size,
+            selection_size: arg.selection_size,
+            num_generation: arg.num_generation,
+            seed: arg.seed,
+            time_budget: arg.time_budget_ms.map(Duration::from_millis),
+            patience: arg.patience,
+            genetic: GeneticConfig {
+                curve: arg.selection_curve,
+                mutation_rate: arg.mutation_rate,
+                selection_exploration: arg.selection_exploration,
+                order_exploration: arg.order_exploration,
+            },
+            ..SolverConfig::default()
+        },
+    ) {
+        Ok(solver) => solver,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+    };
 
-    let tx = match fm.solve(arg.population_size, arg.selection_size, arg.num_generation) {
-        Ok(tx) => tx,
+    if let OutputFormat::Text = arg.output_format {
+        println!("Start solving...");
+    }
+
+    let solution = match fm.solve_with(solver.as_ref()) {
+        Ok(solution) => solution,
         Err(e) => {
             eprintln!("Error: {}", e);
-            return;
+            exit(1);
         }
     };
 
+    if let OutputFormat::Json = arg.output_format {
+        let mut out = stdout().lock();
+        if let Err(e) = solution.write_json(&mut out) {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+        let _ = writeln!(out);
+        return;
+    }
+
     println!("\nThe selected transactions are:");
-    for t in tx {
-        println!("{} -> {}, amount={}, fee={}", t.from, t.to, t.amount, t.fee);
+    for e in &solution.executed {
+        let t = &e.transaction;
+        println!(
+            "request {} tx {}: {} -> {}, amount={} {}, fee={} {}",
+            e.request_id.unwrap_or(e.request as u64),
+            e.index,
+            t.from,
+            t.to,
+            t.amount,
+            t.asset,
+            t.fee,
+            t.fee_asset
+        );
     }
 
+    let explanations = if arg.explain {
+        match fm.explain(&solution) {
+            Ok(explanations) => explanations.into_iter().map(Some).collect(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(1);
+            }
+        }
+    } else {
+        vec![None; solution.rejected.len()]
+    };
+    println!("\nThe rejected transactions are:");
+    for (r, explanation) in solution.rejected.iter().zip(explanations) {
+        print!(
+            "request {} tx {}",
+            r.request_id.unwrap_or(r.request as u64),
+            r.index
+        );
+        match explanation {
+            Some(explanation) => println!(
+                ": {}{}",
+                explanation.reason,
+                competitors(&solution, &explanation.reason)
+            ),
+            None => println!(),
+        }
+    }
+
+    println!(
+        "\nTotal fee: {} {}",
+        solution.total_fee,
+        fm.objective_asset()
+    );
+
+    if !solution.violations.is_empty() {
+        println!("\nThe solution breaks these invariants, the balances are unchanged:");
+        for v in &solution.violations {
+            println!("{v}");
+        }
+    }
+
+    print_balance(fm.balance());
+
+    if let Some(stats) = fm.stats() {
+        println!(
+            "\nSolved by {} in {:?} ({} evaluations{}, {}), using {} transactions of total weight {}.",
+            stats.solver,
+            stats.elapsed,
+            stats.evaluations,
+            if stats.optimal { ", optimal" } else { "" },
+            stats.stop_reason,
+            stats.usage.transactions,
+            stats.usage.weight
+        );
+    }
+}
+
+/// Print the balances sorted by address and asset.
+fn print_balance(balance: &Balance) {
+    let mut balance = balance
+        .iter()
+        .map(|((n, a), b)| (n.to_string(), a.to_string(), b))
+        .collect::<Vec<_>>();
+    balance.sort();
     println!("\nThe user & system balances are:");
-    for (n, b) in fm.balance() {
-        println!("{n}: {b}");
+    for (n, a, b) in balance {
+        println!("{n}: {b} {a}");
     }
 }
 
-#[derive(Deserialize, Clone)]
-struct TxEntry {
-    request: usize,
-    from: String,
-    to: String,
-    amount: f64,
-    fee: f64,
+/// Name the executed transactions a rejection reason refers to by their positions.
+fn competitors(solution: &Solution, reason: &RejectionReason) -> String {
+    let positions = match reason {
+        RejectionReason::Blocked { by, .. } => by.clone(),
+        RejectionReason::LowerFee { alternative, .. } => vec![*alternative],
+        _ => return String::new(),
+    };
+    let names = positions
+        .iter()
+        .map(|p| {
+            let e = &solution.executed[*p];
+            format!(
+                "request {} tx {}",
+                e.request_id.unwrap_or(e.request as u64),
+                e.index
+            )
+        })
+        .collect::<Vec<_>>();
+    format!(" ({})", names.join(", "))
 }
 
-fn write_requests<P: AsRef<Path>>(fm: &mut FeeMaximizer, csv_path: P) -> Result<(), String> {
-    for request in load_test_case(csv_path)
-        .map_err(|e| format!("{e}"))?
-        .into_values()
+/// Print the recomputed fee and balances of a valid solution,
+/// or the first failure and exit with status 1.
+fn verify(arg: VerifyArgs) {
+    let (balance, requests) = arg.inputs.paths();
+    if [balance, requests, &arg.solution]
+        .iter()
+        .filter(|p| **p == "-")
+        .count()
+        > 1
     {
-        let mut req = Request::init_empty();
-        for e in request {
-            req.add_transaction(Transaction {
-                from: Address::from_string(e.from)?,
-                to: Address::from_string(e.to)?,
-                amount: e.amount,
-                fee: e.fee,
-            })?;
-        }
-        fm.add_request(&req);
+        eprintln!("Error: only one of balance, requests and solution can be read from stdin");
+        exit(1);
     }
-    Ok(())
+
+    let mut fm = FeeMaximizer::init_empty();
+    let verification = load(&mut fm, &arg.inputs)
+        .and_then(|_| configure(&mut fm, &arg.block))
+        .and_then(|_| Solution::read_json(open(&arg.solution)?))
+        .and_then(|solution| fm.verify(&solution));
+    let verification = match verification {
+        Ok(verification) => verification,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+    };
+
+    println!(
+        "The solution is valid: {} transactions of total weight {}.",
+        verification.usage.transactions, verification.usage.weight
+    );
+    println!(
+        "\nTotal fee: {} {}",
+        verification.total_fee,
+        fm.objective_asset()
+    );
+    print_balance(&verification.balance);
 }
 
-fn load_test_case<P: AsRef<Path>>(csv_path: P) -> std::io::Result<HashMap<usize, Vec<TxEntry>>> {
-    let mut reader = Reader::from_path(csv_path)?;
-    let mut requests = HashMap::new();
-    for entry in reader.deserialize() {
-        let entry: TxEntry = entry?;
-        requests
-            .entry(entry.request)
-            .and_modify(|o: &mut Vec<TxEntry>| o.push(entry.clone()))
-            .or_insert(vec![entry]);
+/// Write the balance and requests in another format, or the error and exit with status 1.
+fn convert(arg: ConvertArgs) {
+    if arg.inputs.paths() == ("-", "-") {
+        eprintln!("Error: balance and requests cannot both be read from stdin");
+        exit(1);
+    }
+    if (arg.balance_out.as_str(), arg.requests_out.as_str()) == ("-", "-") {
+        eprintln!("Error: balance and requests cannot both be written to stdout");
+        exit(1);
+    }
+
+    let mut fm = FeeMaximizer::init_empty();
+    let converted = load(&mut fm, &arg.inputs).and_then(|_| {
+        let (balance, requests) = (create(&arg.balance_out)?, create(&arg.requests_out)?);
+        match arg.to {
+            InputFormat::Csv => {
+                fm.write_balance_csv(balance)?;
+                fm.write_requests_csv(requests)
+            }
+            InputFormat::Json => {
+                fm.write_balance_json(balance)?;
+                fm.write_requests_json(requests)
+            }
+        }
+    });
+    if let Err(e) = converted {
+        eprintln!("Error: {}", e);
+        exit(1);
     }
-    Ok(requests)
 }
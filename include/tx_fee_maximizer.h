@@ -4,6 +4,76 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Error codes returned by the C interface, 0 means success.
+ */
+#define FEE_MAXIMIZER_OK 0
+
+#define FEE_MAXIMIZER_ERROR_EMPTY_ADDRESS 1
+
+#define FEE_MAXIMIZER_ERROR_EMPTY_ASSET 2
+
+#define FEE_MAXIMIZER_ERROR_SYSTEM_ADDRESS 3
+
+#define FEE_MAXIMIZER_ERROR_NEGATIVE_AMOUNT 4
+
+#define FEE_MAXIMIZER_ERROR_NEGATIVE_BALANCE 5
+
+#define FEE_MAXIMIZER_ERROR_AMOUNT_OVERFLOW 6
+
+#define FEE_MAXIMIZER_ERROR_INVALID_UTF8 7
+
+#define FEE_MAXIMIZER_ERROR_INVALID_SOLVER_PARAMS 8
+
+#define FEE_MAXIMIZER_ERROR_UNKNOWN_SOLVER 9
+
+#define FEE_MAXIMIZER_ERROR_REQUEST_INDEX_OUT_OF_RANGE 10
+
+#define FEE_MAXIMIZER_ERROR_SELF_CONFLICT 11
+
+#define FEE_MAXIMIZER_ERROR_CYCLIC_DEPENDENCY 12
+
+#define FEE_MAXIMIZER_ERROR_CAPACITY_EXCEEDED 13
+
+#define FEE_MAXIMIZER_ERROR_INVALID_SOLUTION 14
+
+#define FEE_MAXIMIZER_ERROR_CSV 15
+
+#define FEE_MAXIMIZER_ERROR_IO 16
+
+#define FEE_MAXIMIZER_ERROR_SOLVER 17
+
+#define FEE_MAXIMIZER_ERROR_JSON 18
+
+#define FEE_MAXIMIZER_ERROR_INSUFFICIENT_BALANCE 19
+
+#define FEE_MAXIMIZER_ERROR_UNKNOWN_TRANSACTION 20
+
+#define FEE_MAXIMIZER_ERROR_CONSTRAINT_VIOLATED 21
+
+#define FEE_MAXIMIZER_ERROR_FEE_MISMATCH 22
+
+/**
+ * Why the last solve stopped searching, see `fee_maximizer_last_stop_reason`.
+ */
+#define FEE_MAXIMIZER_STOP_NONE -1
+
+#define FEE_MAXIMIZER_STOP_COMPLETED 0
+
+#define FEE_MAXIMIZER_STOP_TIME_BUDGET 1
+
+#define FEE_MAXIMIZER_STOP_CONVERGED 2
+
+#define FEE_MAXIMIZER_STOP_CANCELLED 3
+
+/**
+ * A token to stop a solver from another thread (or from a progress callback).
+ *
+ * The solver stops before starting its next generation,
+ * and returns the best solution found so far.
+ */
+typedef struct CancellationToken CancellationToken;
+
 /**
  * the optimizer
  */
@@ -14,17 +84,136 @@ typedef struct FeeMaximizer FeeMaximizer;
  */
 typedef struct Request Request;
 
+/**
+ * Parameters shared by the built-in solvers.
+ *
+ * Each solver only reads the parameters relevant to it.
+ */
+typedef struct SolverConfig SolverConfig;
+
+/**
+ * Amounts and fees are integers in minor units
+ * (one unit is 10^8 minor units).
+ */
 typedef struct CTransaction {
   const char *From;
   const char *To;
-  double Amount;
-  double Fee;
+  int64_t Amount;
+  int64_t Fee;
+  /**
+   * asset of `amount`, the native asset if null.
+   */
+  const char *Asset;
+  /**
+   * asset of `fee`, the native asset if null.
+   */
+  const char *FeeAsset;
+  /**
+   * resource consumed in a block (e.g., gas or byte size).
+   */
+  uint64_t Weight;
 } CTransaction;
 
+/**
+ * Refers to a transaction by its request and its position within the request.
+ */
+typedef struct CTransactionRef {
+  /**
+   * index of the request (as returned by `fee_maximizer_add_request`).
+   */
+  size_t Request;
+  /**
+   * position of the transaction within the request.
+   */
+  size_t Index;
+  /**
+   * whether `request_id` is set.
+   */
+  bool HasRequestId;
+  /**
+   * user-supplied id of the request (see `request_set_id`).
+   */
+  uint64_t RequestId;
+} CTransactionRef;
+
+/**
+ * The balance of an address in an asset.
+ */
+typedef struct CBalance {
+  const char *Address;
+  const char *Asset;
+  /**
+   * balance in minor units.
+   */
+  int64_t Amount;
+} CBalance;
+
+/**
+ * A snapshot of every balance, sorted by address and asset.
+ */
+typedef struct CBalances {
+  struct CBalance *Balances;
+  size_t NBalances;
+} CBalances;
+
+/**
+ * The progress of the genetic solver, reported after every generation.
+ */
+typedef struct CProgress {
+  /**
+   * index of the generation, starting at 0.
+   */
+  size_t Generation;
+  /**
+   * best fee in the objective asset found so far, in minor units.
+   */
+  int64_t BestFee;
+  /**
+   * mean fee of the feasible solutions of the generation, in minor units.
+   */
+  int64_t MeanFee;
+  /**
+   * fraction of the solutions of the generation that are feasible.
+   */
+  double FeasibleFraction;
+} CProgress;
+
 typedef struct CSolution {
   struct CTransaction *Txs;
   size_t NTxs;
   size_t NCapacity;
+  /**
+   * provenance of each transaction in `txs` (`n_txs` entries).
+   */
+  struct CTransactionRef *Provenance;
+  /**
+   * transactions that are not executed, ordered by request and position.
+   */
+  struct CTransactionRef *Rejected;
+  size_t NRejected;
+  /**
+   * indices of the requests none of whose transactions are executed.
+   */
+  size_t *RejectedRequests;
+  size_t NRejectedRequests;
+  /**
+   * total fee collected in the objective asset, in minor units.
+   */
+  int64_t TotalFee;
+  /**
+   * number of executed transactions counted against the block capacity.
+   */
+  size_t UsedTransactions;
+  /**
+   * total weight of the executed transactions.
+   */
+  uint64_t UsedWeight;
+  /**
+   * messages of the invariants broken by the solution in audit mode
+   * (see `fee_maximizer_set_audit`), in which case the balance is unchanged.
+   */
+  const char **Violations;
+  size_t NViolations;
 } CSolution;
 
 #ifdef __cplusplus
@@ -39,13 +228,24 @@ struct Request *request_init(void);
 /**
  * Adds transaction to Request.
  *
- * Return 0 if success, 1 if error.
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
  *
  * The error string is allocated using `malloc` on error and
  * must be freed by the caller using `libc::free`.
  */
 int request_add_transaction(struct Request *req, struct CTransaction tx, char **error);
 
+/**
+ * Set whether the request is atomic,
+ * i.e., its transactions are either all executed or none executed.
+ */
+void request_set_atomic(struct Request *req, bool atomic);
+
+/**
+ * Set a user-supplied id of the request, reported in the solution.
+ */
+void request_set_id(struct Request *req, uint64_t id);
+
 /**
  * Request destructor.
  *
@@ -64,7 +264,7 @@ struct FeeMaximizer *fee_maximizer_init(void);
  *
  * This function add balance from a csv file with two columns (User and balance).
  *
- * Return 0 if success, 1 if error.
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
  *
  * The error string is allocated using `malloc` on error and
  * must be freed by the caller using `libc::free`.
@@ -75,12 +275,97 @@ int fee_maximizer_add_balance_from_csv(struct FeeMaximizer *maximizer,
                                        char **error);
 
 /**
- * Add a request to fee maximizer.
+ * Fee maximizer add requests.
+ *
+ * This function add requests from a csv file with columns request, from, to,
+ * amount and fee, and the optional columns atomic, weight, asset and fee_asset.
+ * The `request` column is used as request id, and requests are added
+ * in the order of their first row.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_add_requests_from_csv(struct FeeMaximizer *maximizer,
+                                        const char *requests_csv,
+                                        bool header,
+                                        char **error);
+
+/**
+ * Fee maximizer add balance from an in-memory csv buffer.
+ *
+ * Same as `fee_maximizer_add_balance_from_csv`, but reads the csv from
+ * the `len` bytes at `data` instead of a file. `data` may be null if `len` is 0.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_add_balance_from_csv_buffer(struct FeeMaximizer *maximizer,
+                                              const uint8_t *data,
+                                              size_t len,
+                                              bool header,
+                                              char **error);
+
+/**
+ * Fee maximizer add requests from an in-memory csv buffer.
+ *
+ * Same as `fee_maximizer_add_requests_from_csv`, but reads the csv from
+ * the `len` bytes at `data` instead of a file. `data` may be null if `len` is 0.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_add_requests_from_csv_buffer(struct FeeMaximizer *maximizer,
+                                               const uint8_t *data,
+                                               size_t len,
+                                               bool header,
+                                               char **error);
+
+/**
+ * Add a request to fee maximizer, and return the index of the request.
  *
  * Note: this function only borrows request,
  * so it is safe to free request after calling this function.
  */
-void fee_maximizer_add_request(struct FeeMaximizer *maximizer, const struct Request *req);
+size_t fee_maximizer_add_request(struct FeeMaximizer *maximizer, const struct Request *req);
+
+/**
+ * Request `request` may only be included if request `depends_on`
+ * is included and executed before it.
+ *
+ * Requests are referred to by the index returned by `fee_maximizer_add_request`.
+ * Out of range indices and cyclic dependencies are reported when solving.
+ */
+void fee_maximizer_add_dependency(struct FeeMaximizer *maximizer,
+                                  size_t request,
+                                  size_t depends_on);
+
+/**
+ * Requests `a` and `b` cannot be both included.
+ *
+ * Requests are referred to by the index returned by `fee_maximizer_add_request`.
+ */
+void fee_maximizer_add_conflict(struct FeeMaximizer *maximizer, size_t a, size_t b);
+
+/**
+ * Limit the number of transactions and the total weight of the solution.
+ *
+ * A limit of 0 means no limit.
+ */
+void fee_maximizer_set_block_capacity(struct FeeMaximizer *maximizer,
+                                      size_t max_transactions,
+                                      uint64_t max_weight);
+
+/**
+ * Check the invariants of every solution (on by default in debug builds):
+ * broken invariants are reported in the `violations` of the solution.
+ */
+void fee_maximizer_set_audit(struct FeeMaximizer *maximizer, bool audit);
 
 /**
  * Fee maximizer solve and get result.
@@ -90,7 +375,8 @@ void fee_maximizer_add_request(struct FeeMaximizer *maximizer, const struct Requ
  * - selection_size: number of individuals selected for the next generation.
  * - max_generation: maximum number of generations.
  *
- * The returned pointer is `nullptr` if an error occurs.
+ * The returned pointer is `nullptr` if an error occurs,
+ * and the error code is given by `fee_maximizer_last_error_code`.
  *
  * The error string is allocated using `malloc` on error and
  * must be freed by the caller using `libc::free`.
@@ -102,12 +388,243 @@ struct CSolution *fee_maximizer_solve(struct FeeMaximizer *maximizer,
                                       char **error);
 
 /**
- * Query address balance.
+ * Solver config constructor, initialized with the default parameters.
+ */
+struct SolverConfig *solver_config_init(void);
+
+/**
+ * Set the genetic algorithm parameters of a solver config.
  *
- * Return -200.0 when address is not a valid UTF-8 string.
- * Return -1.0 when address not found.
+ * - population_size: number of individuals in the population.
+ * - selection_size: number of individuals selected for the next generation.
+ * - max_generation: maximum number of generations.
+ */
+void solver_config_set_genetic_params(struct SolverConfig *config,
+                                      size_t population_size,
+                                      size_t selection_size,
+                                      size_t max_generation);
+
+/**
+ * Set the rng seed of a solver config, so that solving is reproducible.
  */
-double fee_maximizer_query_address_balance(struct FeeMaximizer *maximizer, const char *address);
+void solver_config_set_seed(struct SolverConfig *config, uint64_t seed);
+
+/**
+ * Limit the wall-clock time of the genetic solver, in milliseconds.
+ *
+ * The best solution found so far is returned when the time runs out.
+ */
+void solver_config_set_time_budget(struct SolverConfig *config, uint64_t millis);
+
+/**
+ * Stop the genetic solver after `patience` generations without improvement of the best fee.
+ */
+void solver_config_set_patience(struct SolverConfig *config, size_t patience);
+
+/**
+ * Set the selection curve of the genetic solver from its name:
+ * `linear`, `sigmoid`, `sigmoid:<steepness>`, `power` or `power:<exponent>`.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int solver_config_set_selection_curve(struct SolverConfig *config,
+                                      const char *curve,
+                                      char **error);
+
+/**
+ * Use `curve(weight, user_data)` as the selection curve of the genetic solver,
+ * mapping a weight between 0 and 1 to a selection probability.
+ *
+ * The curve is called concurrently from the solver threads.
+ * Pass a null curve to go back to the linear curve.
+ */
+void solver_config_set_custom_selection_curve(struct SolverConfig *config,
+                                              double (*curve)(double weight, void *user_data),
+                                              void *user_data);
+
+/**
+ * Set the minimum probability of selecting, and of dropping,
+ * any transaction in the genetic solver, between 0 and 0.5.
+ */
+void solver_config_set_mutation_rate(struct SolverConfig *config, double mutation_rate);
+
+/**
+ * Set the probabilities, between 0 and 1, that an agent of the genetic solver
+ * ignores the learned weights to select transactions, and to order requests.
+ */
+void solver_config_set_exploration(struct SolverConfig *config, double selection, double order);
+
+/**
+ * Call `callback` with the progress and `user_data` after every generation
+ * of the genetic solver, on the thread calling `fee_maximizer_solve_with`.
+ *
+ * The progress pointer is only valid during the call. Pass a null callback to remove it.
+ */
+void solver_config_set_progress_callback(struct SolverConfig *config,
+                                         void (*callback)(const struct CProgress *progress,
+                                                          void *user_data),
+                                         void *user_data);
+
+/**
+ * Stop the genetic solver when `token` is cancelled.
+ *
+ * The config shares the token, so it is safe to destroy the token after this call,
+ * and cancelling any handle to it stops the solves using this config.
+ */
+void solver_config_set_cancellation_token(struct SolverConfig *config,
+                                          const struct CancellationToken *token);
+
+/**
+ * Cancellation token constructor.
+ */
+struct CancellationToken *cancellation_token_init(void);
+
+/**
+ * Cancel a token, e.g. from another thread or from a progress callback.
+ *
+ * The solvers using it stop before their next generation,
+ * and return the best solution found so far.
+ */
+void cancellation_token_cancel(const struct CancellationToken *token);
+
+/**
+ * Cancellation token destructor.
+ *
+ * Safety: do never double call!
+ */
+void cancellation_token_destroy(struct CancellationToken *token);
+
+/**
+ * Solver config destructor.
+ *
+ * Safety: do never double call!
+ */
+void solver_config_destroy(struct SolverConfig *config);
+
+/**
+ * Fee maximizer solve with a solver selected by name, and get result.
+ *
+ * Available solvers: "genetic", "greedy", "exact".
+ *
+ * Note: this function only borrows config,
+ * so it is safe to free config after calling this function.
+ *
+ * The returned pointer is `nullptr` if an error occurs,
+ * and the error code is given by `fee_maximizer_last_error_code`.
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+struct CSolution *fee_maximizer_solve_with(struct FeeMaximizer *maximizer,
+                                           const char *solver_name,
+                                           const struct SolverConfig *config,
+                                           char **error);
+
+/**
+ * Get why the last successful solve stopped searching (see `FEE_MAXIMIZER_STOP_*`),
+ * or `FEE_MAXIMIZER_STOP_NONE` if nothing was solved yet.
+ */
+int fee_maximizer_last_stop_reason(const struct FeeMaximizer *maximizer);
+
+/**
+ * Get the number of generations run by the last successful solve,
+ * 0 if nothing was solved yet or the solver is not genetic.
+ */
+size_t fee_maximizer_last_generations(const struct FeeMaximizer *maximizer);
+
+/**
+ * Get the total fee of the last successful solve in the objective asset,
+ * in minor units, 0 if nothing was solved yet.
+ */
+int64_t fee_maximizer_last_best_fee(const struct FeeMaximizer *maximizer);
+
+/**
+ * Maximize the fees paid in `asset` (the native asset by default).
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_set_objective_asset(struct FeeMaximizer *maximizer,
+                                      const char *asset,
+                                      char **error);
+
+/**
+ * Query address balance of the native asset in minor units.
+ *
+ * Return -200 when address is not a valid UTF-8 string.
+ * Return -1 when address not found.
+ */
+int64_t fee_maximizer_query_address_balance(struct FeeMaximizer *maximizer, const char *address);
+
+/**
+ * Query address balance of an asset in minor units.
+ *
+ * Return -200 when address or asset is not a valid UTF-8 string.
+ * Return -1 when the address does not hold the asset.
+ */
+int64_t fee_maximizer_query_address_asset_balance(struct FeeMaximizer *maximizer,
+                                                  const char *address,
+                                                  const char *asset);
+
+/**
+ * Set the balance of an address in an asset (the native asset if null or empty)
+ * in minor units, replacing any previous balance.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_set_balance(struct FeeMaximizer *maximizer,
+                              const char *address,
+                              const char *asset,
+                              int64_t amount,
+                              char **error);
+
+/**
+ * Add to the balance of an address in an asset (the native asset if null or empty)
+ * in minor units, like every row of a balance csv.
+ *
+ * Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+ *
+ * The error string is allocated using `malloc` on error and
+ * must be freed by the caller using `libc::free`.
+ */
+int fee_maximizer_add_balance(struct FeeMaximizer *maximizer,
+                              const char *address,
+                              const char *asset,
+                              int64_t amount,
+                              char **error);
+
+/**
+ * Remove the balance of an address in an asset (the native asset if null or empty).
+ *
+ * Return the removed balance in minor units, or -1 when the address does not hold the asset.
+ */
+int64_t fee_maximizer_remove_balance(struct FeeMaximizer *maximizer,
+                                     const char *address,
+                                     const char *asset);
+
+/**
+ * Get a snapshot of every balance, sorted by address and asset,
+ * e.g. to read the final balances after solving.
+ *
+ * The snapshot does not change with the fee maximizer,
+ * and must be freed with `balances_destroy`.
+ */
+struct CBalances *fee_maximizer_balances(const struct FeeMaximizer *maximizer);
+
+/**
+ * Balance snapshot destructor.
+ *
+ * Safety: do never double call!
+ */
+void balances_destroy(struct CBalances *balances);
 
 /**
  * Solution destructor.
@@ -116,6 +633,14 @@ double fee_maximizer_query_address_balance(struct FeeMaximizer *maximizer, const
  */
 void solution_destroy(struct CSolution *sol);
 
+/**
+ * Get the error code of the last failed call on the current thread,
+ * or `FEE_MAXIMIZER_OK` if no call has failed.
+ *
+ * Useful for functions returning `nullptr` on error.
+ */
+int fee_maximizer_last_error_code(void);
+
 /**
  * Fee maximizer destructor.
  *
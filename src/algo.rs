@@ -1,121 +1,292 @@
-///! This file defines the optimization algorithm for the fee maximizer.
-use crate::{Address, Request, Transaction, SYSTEM_ADDRESS};
+//! This file defines the optimization algorithm for the fee maximizer.
+use crate::constraints::RequestGraph;
+use crate::dense::{apply_dense_transaction, DenseProblem};
+use crate::solver::RequestWeights;
+use crate::{
+    Amount, Balance, BlockUsage, GeneticConfig, Problem, Progress, Request, SolverConfig,
+    StopReason, Transaction, WarmStart, SYSTEM_ADDRESS,
+};
 use fastrand::Rng;
 use rayon::prelude::*;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+/// (dense balance after execution, system balance in the objective asset,
+/// selected transactions, request order)
+type AgentResult = (Option<Vec<Amount>>, Amount, Vec<Vec<bool>>, Vec<usize>);
+
+/// The weights learned from the previous generations.
+struct Weights {
+    /// how often every transaction is selected.
+    selection: Vec<Vec<usize>>,
+    /// the weight of a transaction selected by every agent.
+    selection_upper_bound: usize,
+    /// how early every request is executed.
+    order: Vec<usize>,
+    /// the maximum order weight, at least 1.
+    order_upper_bound: usize,
+}
+
+/// The result of the genetic search.
+pub(crate) struct GeneticRun {
+    /// selected (request index, transaction index) pairs in execution order.
+    pub selected: Vec<(usize, usize)>,
+    pub balance: Balance,
+    /// number of generations run.
+    pub generations: usize,
+    pub stop_reason: StopReason,
+    /// the weights of the last generation, to warm-start a later solve.
+    pub warm_start: WarmStart,
+}
+
+/// The upper bound of the weights initialized from a warm start.
+const WARM_START_SCALE: f64 = 1000.0;
 
 pub(crate) fn maximize_fee(
-    balance: &HashMap<Address, f64>,
-    requests: &[Request],
-    population_size: usize,
-    selection_size: usize,
-    num_generation: usize,
-) -> (Vec<Transaction>, HashMap<Address, f64>) {
+    problem: &Problem,
+    graph: &RequestGraph,
+    config: &SolverConfig,
+) -> GeneticRun {
+    let start = Instant::now();
+    let balance = problem.balance;
+    // fees are the increase of the system balance in the objective asset.
+    let initial_system_balance = balance
+        .get(&(SYSTEM_ADDRESS.clone(), problem.objective.clone()))
+        .map_or(0, |b| b.minor());
+    let requests = problem.requests;
+    let population_size = config.population_size;
+    // at least one elite is kept, which is the returned solution.
+    let selection_size = config.selection_size.max(1);
     assert!(population_size >= selection_size);
+    // every agent derives its own rng from this seed,
+    // so that the result does not depend on thread scheduling.
+    let seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+    // intern the balances once, so that agents do not clone and hash them.
+    let dense = DenseProblem::new(problem);
 
     // initialize the global weights.
-    let mut selection_weights = requests
-        .iter()
-        .map(|r| vec![1usize; r.0.len()])
-        .collect::<Vec<_>>();
-    let mut weight_upper_bound = 2usize;
-    let mut request_order_weight = vec![1usize; requests.len()];
-    let mut order_max_weight_limit = 2usize;
+    let mut weights = match problem.warm_start {
+        Some(warm_start) => warm_weights(requests, warm_start),
+        None => Weights {
+            selection: requests
+                .iter()
+                .map(|r| vec![1usize; r.transactions.len()])
+                .collect(),
+            selection_upper_bound: 2,
+            order: vec![1usize; requests.len()],
+            order_upper_bound: 2,
+        },
+    };
 
-    let mut current_best_result: Option<(
-        Option<HashMap<Address, f64>>,
-        f64,
-        Vec<Vec<bool>>,
-        Vec<usize>,
-    )> = None;
+    // the best feasible results over all generations, best first.
+    let mut elite: Vec<AgentResult> = Vec::with_capacity(selection_size);
+    let mut best_system_balance: Option<Amount> = None;
+    let mut generations = 0;
+    let mut generations_without_improvement = 0;
+    let mut generation_time = Duration::ZERO;
+    let mut stop_reason = StopReason::Completed;
 
     let mut agents_results = Vec::new();
-    for _ in 0..num_generation {
+    for generation in 0..config.num_generation {
+        if config
+            .cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            stop_reason = StopReason::Cancelled;
+            break;
+        }
+        // only start a generation if it is expected to finish within the budget.
+        if let Some(budget) = config.time_budget {
+            if generation > 0 && start.elapsed() + generation_time > budget {
+                stop_reason = StopReason::TimeBudget;
+                break;
+            }
+        }
+        let generation_start = Instant::now();
+
         // let agents search for the best solution.
         (0..population_size)
             .into_par_iter()
-            .map(|_| {
+            .map(|agent| {
                 search_agent(
-                    balance.clone(),
-                    requests,
-                    &selection_weights,
-                    weight_upper_bound,
-                    &request_order_weight,
-                    order_max_weight_limit,
+                    problem,
+                    &dense,
+                    graph,
+                    &weights,
+                    &config.genetic,
+                    &Rng::with_seed(agent_seed(seed, generation, agent)),
                 )
             })
             .collect_into_vec(&mut agents_results);
 
+        // fees of the feasible agents, only needed to report the progress.
+        let fees = config.progress.as_ref().map(|_| {
+            agents_results
+                .iter()
+                .filter(|(bal, _, _, _)| bal.is_some())
+                .map(|(_, system_balance, _, _)| system_balance.minor() - initial_system_balance)
+                .collect::<Vec<_>>()
+        });
+
         // make selection.
 
-        // sort agents_results by system balance from large to small.
-        agents_results.sort_unstable_by(|(_, a, _, _), (_, b, _, _)| {
-            if a.is_nan() {
-                return Ordering::Less;
+        // the selection is drawn from the feasible agents of this generation
+        // and from the elite carried over, so the best solution is never lost.
+        // on ties, the elite is kept first.
+        let mut candidates = std::mem::take(&mut elite);
+        candidates.extend(
+            agents_results
+                .drain(..)
+                .filter(|(bal, _, _, _)| bal.is_some()),
+        );
+        // sort by system balance from large to small.
+        candidates.sort_by_key(|(_, system_balance, _, _)| Reverse(*system_balance));
+        for candidate in candidates {
+            if elite.len() == selection_size {
+                break;
             }
-            if b.is_nan() {
-                return Ordering::Greater;
+            if !elite.iter().any(|e| is_same_agent(e, &candidate)) {
+                elite.push(candidate);
             }
-            a.partial_cmp(b).unwrap_or(Ordering::Equal).reverse()
-        });
-        agents_results.truncate(selection_size);
-        current_best_result = agents_results.first().map(Clone::clone);
+        }
+        let elite_best = elite
+            .first()
+            .map(|(_, system_balance, _, _)| *system_balance);
+        if elite_best > best_system_balance {
+            best_system_balance = elite_best;
+            generations_without_improvement = 0;
+        } else {
+            generations_without_improvement += 1;
+        }
+        if let (Some(progress), Some(fees)) = (&config.progress, fees) {
+            let best_fee = best_system_balance.map_or(0, |b| b.minor() - initial_system_balance);
+            let mean_fee = match fees.len() {
+                0 => 0,
+                n => (fees.iter().map(|f| *f as i128).sum::<i128>() / n as i128) as i64,
+            };
+            progress.call(&Progress {
+                generation,
+                best_fee: Amount::from_minor(best_fee),
+                mean_fee: Amount::from_minor(mean_fee),
+                feasible_fraction: fees.len() as f64 / population_size.max(1) as f64,
+            });
+        }
 
         // crossover the selected transactions.
 
         // reset the global weights.
-        selection_weights
+        weights
+            .selection
             .iter_mut()
             .for_each(|e| e.iter_mut().for_each(|w| *w = 0));
-        request_order_weight.iter_mut().for_each(|w| *w = 0);
+        weights.order.iter_mut().for_each(|w| *w = 0);
 
         // update the global weights.
-        agents_results
-            .iter()
-            .filter(|(bal, _, _, _)| bal.is_some())
-            .for_each(|(_, _, selected, request_order)| {
-                update_global_states(
-                    &mut selection_weights,
-                    &mut request_order_weight,
-                    &mut order_max_weight_limit,
-                    &selected,
-                    &request_order,
-                );
-            });
-        // set weight_upper_bound to the maximum weight.
-        weight_upper_bound = selection_size;
+        elite.iter().for_each(|(_, _, selected, request_order)| {
+            update_global_states(&mut weights, selected, request_order);
+        });
+        // set the selection upper bound to the maximum weight.
+        weights.selection_upper_bound = selection_size;
+
+        generations += 1;
+        generation_time = generation_start.elapsed();
+        if config
+            .patience
+            .is_some_and(|patience| generations_without_improvement >= patience)
+        {
+            stop_reason = StopReason::Converged;
+            break;
+        }
     }
-    match current_best_result {
+    let (selected, balance) = match elite.into_iter().next() {
         Some((Some(bal), _, selected, request_order)) => {
-            let mut txs = Vec::new();
-            for req in request_order.iter().copied() {
-                let request = &requests[req];
-                let selection = &selected[req];
-                for (tx, selected) in request.0.iter().zip(selection.iter().copied()) {
-                    if selected {
-                        txs.push(tx.clone());
-                    }
-                }
-            }
-            (txs, bal)
+            let txs: Vec<_> = request_order
+                .iter()
+                .flat_map(|req| {
+                    selected[*req]
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, s)| **s)
+                        .map(|(tx, _)| (*req, tx))
+                })
+                .collect();
+            let balance = dense.to_balance(balance, &bal, &txs);
+            (txs, balance)
         }
         _ => (vec![], balance.clone()),
+    };
+    GeneticRun {
+        selected,
+        balance,
+        generations,
+        stop_reason,
+        warm_start: learned_weights(requests, &weights),
     }
 }
 
+/// Initialize the weights from a warm start, matching requests by id.
+///
+/// The learned fractions are blended halfway with the uniform prior of 0.5,
+/// so that no transaction is ruled out after the balances changed,
+/// and requests without learned weights start from the prior.
+fn warm_weights(requests: &[Request], warm_start: &WarmStart) -> Weights {
+    let weight = |fraction: f64| ((fraction + 0.5) / 2.0 * WARM_START_SCALE).round() as usize;
+    let learned = |request: &Request| {
+        request
+            .id
+            .and_then(|id| warm_start.requests.get(&id))
+            .filter(|w| w.selection.len() == request.transactions.len())
+    };
+    Weights {
+        selection: requests
+            .iter()
+            .map(|r| match learned(r) {
+                Some(w) => w.selection.iter().map(|f| weight(*f)).collect(),
+                None => vec![weight(0.5); r.transactions.len()],
+            })
+            .collect(),
+        selection_upper_bound: WARM_START_SCALE as usize,
+        order: requests
+            .iter()
+            .map(|r| weight(learned(r).map_or(0.5, |w| w.order)))
+            .collect(),
+        order_upper_bound: WARM_START_SCALE as usize,
+    }
+}
+
+/// The weights of the requests with an id, as fractions of their upper bounds.
+fn learned_weights(requests: &[Request], weights: &Weights) -> WarmStart {
+    let fraction = |w: usize, upper_bound: usize| (w as f64 / upper_bound.max(1) as f64).min(1.0);
+    WarmStart {
+        requests: requests
+            .iter()
+            .enumerate()
+            .filter_map(|(req, request)| {
+                let weights = RequestWeights {
+                    selection: weights.selection[req]
+                        .iter()
+                        .map(|w| fraction(*w, weights.selection_upper_bound))
+                        .collect(),
+                    order: fraction(weights.order[req], weights.order_upper_bound),
+                };
+                request.id.map(|id| (id, weights))
+            })
+            .collect(),
+    }
+}
+
+/// Whether two agents selected the same transactions in the same order.
+fn is_same_agent(a: &AgentResult, b: &AgentResult) -> bool {
+    a.1 == b.1 && a.2 == b.2 && a.3 == b.3
+}
+
 /// Crossover the selected transactions.
-fn update_global_states(
-    selection_weights: &mut [Vec<usize>],
-    request_order_weight: &mut [usize],
-    order_max_weight_limit: &mut usize,
-    selected: &[Vec<bool>],
-    request_order: &[usize],
-) {
+fn update_global_states(weights: &mut Weights, selected: &[Vec<bool>], request_order: &[usize]) {
     // update the global weights.
     selected
         .iter()
-        .zip(selection_weights.iter_mut())
+        .zip(weights.selection.iter_mut())
         .for_each(|(selection, weights)| {
             selection
                 .iter()
@@ -128,98 +299,175 @@ fn update_global_states(
     // update the request order weight.
     let total_requests = request_order.len();
     request_order.iter().enumerate().for_each(|(i, req)| {
-        request_order_weight[*req] += total_requests - i - 1;
+        weights.order[*req] += total_requests - i - 1;
     });
-    *order_max_weight_limit = request_order_weight
-        .iter()
-        .max()
-        .copied()
-        .unwrap_or(1)
-        .max(1); // at least 1.
+    let max_order_weight = weights.order.iter().max().copied().unwrap_or(1);
+    weights.order_upper_bound = max_order_weight.max(1); // at least 1.
 }
 
 /// A single agent search for the best solution.
-pub fn search_agent(
-    balance: HashMap<Address, f64>,
-    requests: &[Request],
-    initial_weights: &[Vec<usize>],
-    weight_upper_bound: usize,
-    request_order_weight: &Vec<usize>,
-    order_max_weight_limit: usize,
-) -> (
-    Option<HashMap<Address, f64>>,
-    f64,
-    Vec<Vec<bool>>,
-    Vec<usize>,
-) {
-    let rng = Rng::new();
-    let curve = |x: f64| x;
-    let request_order =
-        prioritized_left_shuffling(request_order_weight.clone(), order_max_weight_limit);
-    let selected = random_selection(initial_weights, weight_upper_bound, curve, &rng);
-    let balance = evaluate(balance, requests, &selected, &request_order);
+fn search_agent(
+    problem: &Problem,
+    dense: &DenseProblem,
+    graph: &RequestGraph,
+    weights: &Weights,
+    genetic: &GeneticConfig,
+    rng: &Rng,
+) -> AgentResult {
+    // only draw when exploring, so that the defaults keep the same random streams.
+    let explore = |rate: f64| rate > 0.0 && rng.f64() < rate;
+    let request_order = if explore(genetic.order_exploration) {
+        // with equal weights, the order is uniform.
+        let equal_weights = vec![1; weights.order.len()];
+        if graph.has_dependencies() {
+            prioritized_topological_shuffling(&equal_weights, 1, graph, rng)
+        } else {
+            prioritized_left_shuffling(equal_weights, 1, rng)
+        }
+    } else if graph.has_dependencies() {
+        prioritized_topological_shuffling(&weights.order, weights.order_upper_bound, graph, rng)
+    } else {
+        prioritized_left_shuffling(weights.order.clone(), weights.order_upper_bound, rng)
+    };
+    let explore_selection = explore(genetic.selection_exploration);
+    let mutation_rate = genetic.mutation_rate;
+    let curve = |x: f64| {
+        if explore_selection {
+            0.5
+        } else {
+            genetic
+                .curve
+                .apply(x)
+                .clamp(mutation_rate, 1.0 - mutation_rate)
+        }
+    };
+    let mut selected = random_selection(
+        problem.requests,
+        &weights.selection,
+        weights.selection_upper_bound,
+        curve,
+        rng,
+    );
+    repair_selection(&mut selected, graph, rng);
+    if !problem.capacity.is_unlimited() {
+        fit_capacity(&mut selected, problem, &request_order);
+        drop_missing_dependencies(&mut selected, graph);
+    }
+    let balance = evaluate(
+        dense.balance.clone(),
+        problem,
+        dense,
+        &selected,
+        &request_order,
+    );
     let system_balance = balance
         .as_ref()
-        .map(|b| b.get(&SYSTEM_ADDRESS).copied().unwrap_or(0.0))
-        .unwrap_or(-1.0);
+        .map(|b| b[dense.system])
+        .unwrap_or(Amount::from_minor(-1));
     (balance, system_balance, selected, request_order)
 }
 
-/// Evaluate the selected transactions.
-/// Return the balance after the transactions.
-pub fn evaluate(
-    mut balance: HashMap<Address, f64>,
-    requests: &[Request],
+/// Evaluate the selected transactions, starting from the dense `balance`.
+/// Return the dense balance after the transactions,
+/// or None if the selection is infeasible, violates the constraints,
+/// or exceeds the block capacity.
+fn evaluate(
+    mut balance: Vec<Amount>,
+    problem: &Problem,
+    dense: &DenseProblem,
     selected: &[Vec<bool>],
     request_order: &[usize],
-) -> Option<HashMap<Address, f64>> {
+) -> Option<Vec<Amount>> {
+    let requests = problem.requests;
+    let constraints = problem.constraints;
+    if !problem.capacity.is_unlimited() {
+        let usage = request_order
+            .iter()
+            .flat_map(|req| requests[*req].transactions.iter().zip(&selected[*req]))
+            .filter(|(_, s)| **s)
+            .fold(BlockUsage::default(), |usage, (tx, _)| {
+                usage.with_transaction(tx.weight)
+            });
+        if !problem.capacity.fits(usage) {
+            return None;
+        }
+    }
+    if !constraints.is_empty() {
+        let included = selected
+            .iter()
+            .map(|s| s.contains(&true))
+            .collect::<Vec<_>>();
+        if !constraints.is_satisfied(&included, request_order) {
+            return None;
+        }
+    }
     for req in request_order.iter().copied() {
         let request = &requests[req];
         let selection = &selected[req];
-        for (tx, selected) in request.0.iter().zip(selection.iter().copied()) {
-            if selected {
-                let mut balance_sufficient = false;
-                balance
-                    .entry(tx.from.clone())
-                    .and_modify(|e| {
-                        if *e - tx.amount - tx.fee >= 0.0 {
-                            *e -= tx.amount + tx.fee;
-                            balance_sufficient = true;
-                        }
-                    })
-                    .or_insert_with(|| {
-                        if tx.amount + tx.fee <= 0.0 {
-                            balance_sufficient = true;
-                        }
-                        0.0
-                    });
-                if balance_sufficient {
-                    balance
-                        .entry(SYSTEM_ADDRESS.clone())
-                        .and_modify(|e| *e += tx.fee)
-                        .or_insert(tx.fee);
-                    balance
-                        .entry(tx.to.clone())
-                        .and_modify(|e| *e += tx.amount)
-                        .or_insert(tx.amount);
-                } else {
-                    // return None if the balance is not sufficient.
-                    return None;
-                }
+        // atomic requests cannot be partially executed.
+        if request.atomic && selection.contains(&true) && selection.contains(&false) {
+            return None;
+        }
+        for (tx, selected) in dense.requests[req].iter().zip(selection.iter().copied()) {
+            // return None if the balance is not sufficient.
+            if selected && !apply_dense_transaction(&mut balance, tx) {
+                return None;
             }
         }
     }
     Some(balance)
 }
 
+/// Apply a single transaction to the balance.
+///
+/// The sender pays the amount in `tx.asset` and the fee in `tx.fee_asset`.
+/// Return false if the balance of the sender is not sufficient
+/// (or if a balance overflows), in which case the balance should be discarded.
+pub(crate) fn apply_transaction(balance: &mut Balance, tx: &Transaction) -> bool {
+    for (asset, amount) in [(&tx.asset, tx.amount), (&tx.fee_asset, tx.fee)] {
+        let from_balance = balance
+            .entry((tx.from.clone(), asset.clone()))
+            .or_insert(Amount::ZERO);
+        match from_balance.checked_sub(amount) {
+            Some(remaining) if !remaining.is_negative() => *from_balance = remaining,
+            _ => return false,
+        }
+    }
+    for (address, asset, amount) in [
+        (&*SYSTEM_ADDRESS, &tx.fee_asset, tx.fee),
+        (&tx.to, &tx.asset, tx.amount),
+    ] {
+        let entry = balance
+            .entry((address.clone(), asset.clone()))
+            .or_insert(Amount::ZERO);
+        match entry.checked_add(amount) {
+            Some(new_balance) => *entry = new_balance,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Randomly select transactions, each with a probability given by its weight.
+///
+/// Transactions of an atomic request share the same weight,
+/// so they are selected or dropped together with a single draw.
 fn random_selection<F: Fn(f64) -> f64>(
+    requests: &[Request],
     weights: &[Vec<usize>],
     weight_upper_bound: usize,
     curve: F,
     rng: &Rng,
 ) -> Vec<Vec<bool>> {
     let mut selection = Vec::with_capacity(weights.len());
-    for weight in weights.iter() {
+    for (request, weight) in requests.iter().zip(weights.iter()) {
+        if request.atomic {
+            let w = weight.first().copied().unwrap_or(0);
+            let threshold = curve(w as f64 / weight_upper_bound as f64);
+            let rand_f64 = rng.f64();
+            selection.push(vec![rand_f64 < threshold; weight.len()]);
+            continue;
+        }
         let mut tx_selected = Vec::with_capacity(weight.len());
         for w in weight.iter().copied() {
             let threshold = curve(w as f64 / weight_upper_bound as f64);
@@ -231,14 +479,128 @@ fn random_selection<F: Fn(f64) -> f64>(
     selection
 }
 
+/// Drop the requests that violate conflicts or dependencies from a selection.
+///
+/// For each pair of conflicting requests, one of them is dropped at random.
+/// Then requests missing a dependency are dropped.
+fn repair_selection(selection: &mut [Vec<bool>], graph: &RequestGraph, rng: &Rng) {
+    for a in 0..selection.len() {
+        for b in graph.conflicts[a].iter().copied() {
+            if a < b && selection[a].contains(&true) && selection[b].contains(&true) {
+                drop_request(selection, if rng.bool() { a } else { b });
+            }
+        }
+    }
+    drop_missing_dependencies(selection, graph);
+}
+
+/// Drop the requests whose dependencies are not included.
+///
+/// Requests are visited in topological order, so that dropping
+/// a request also drops the requests depending on it.
+fn drop_missing_dependencies(selection: &mut [Vec<bool>], graph: &RequestGraph) {
+    for r in graph.topological_order.iter().copied() {
+        if selection[r].contains(&true)
+            && graph.depends_on[r]
+                .iter()
+                .any(|d| !selection[*d].contains(&true))
+        {
+            drop_request(selection, r);
+        }
+    }
+}
+
+/// Fill the block in execution order, dropping the selected transactions
+/// that do not fit in the remaining capacity (whole atomic requests at once).
+fn fit_capacity(selection: &mut [Vec<bool>], problem: &Problem, request_order: &[usize]) {
+    let mut usage = BlockUsage::default();
+    for req in request_order.iter().copied() {
+        let request = &problem.requests[req];
+        if request.atomic {
+            let request_usage = request
+                .transactions
+                .iter()
+                .zip(&selection[req])
+                .filter(|(_, s)| **s)
+                .fold(usage, |usage, (tx, _)| usage.with_transaction(tx.weight));
+            if problem.capacity.fits(request_usage) {
+                usage = request_usage;
+            } else {
+                drop_request(selection, req);
+            }
+            continue;
+        }
+        for (tx, selected) in request.transactions.iter().zip(selection[req].iter_mut()) {
+            if *selected {
+                let new_usage = usage.with_transaction(tx.weight);
+                if problem.capacity.fits(new_usage) {
+                    usage = new_usage;
+                } else {
+                    *selected = false;
+                }
+            }
+        }
+    }
+}
+
+fn drop_request(selection: &mut [Vec<bool>], r: usize) {
+    selection[r].iter_mut().for_each(|s| *s = false);
+}
+
+/// Prioritized shuffling that only places a request after all its dependencies.
+///
+/// At each step, the next request is drawn by roulette wheel selection
+/// among the requests whose dependencies are all placed.
+fn prioritized_topological_shuffling(
+    data: &[usize],
+    max_weight_limit: usize,
+    graph: &RequestGraph,
+    rng: &Rng,
+) -> Vec<usize> {
+    let mut pending = graph.depends_on.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut ready = (0..data.len())
+        .filter(|r| pending[*r] == 0)
+        .collect::<Vec<_>>();
+    let mut ready_weights = Vec::with_capacity(data.len());
+    let mut order = Vec::with_capacity(data.len());
+    while !ready.is_empty() {
+        ready_weights.clear();
+        ready_weights.extend(ready.iter().map(|r| data[*r]));
+        let index = if ready_weights.iter().all(|w| *w == 0) {
+            rng.usize(..ready.len())
+        } else {
+            roulette_wheel_selection(&ready_weights, 0, max_weight_limit, rng)
+        };
+        let req = ready.swap_remove(index);
+        order.push(req);
+        for dependent in graph.dependents[req].iter().copied() {
+            pending[dependent] -= 1;
+            if pending[dependent] == 0 {
+                ready.push(dependent);
+            }
+        }
+    }
+    order
+}
+
 /// Shuffling with a certain priority.
 ///
 /// [prioritized left shuffling](https://stackoverflow.com/questions/67648335/how-to-write-a-prioritized-left-shuffle-algorithm-in-on)
-fn prioritized_left_shuffling(mut data: Vec<usize>, max_weight_limit: usize) -> Vec<usize> {
-    let rng = Rng::new();
+fn prioritized_left_shuffling(
+    mut data: Vec<usize>,
+    max_weight_limit: usize,
+    rng: &Rng,
+) -> Vec<usize> {
     let mut order = (0..data.len()).collect::<Vec<_>>();
+    // the weight left to place, when it is 0 the rest is placed uniformly.
+    let mut remaining_weight = data.iter().sum::<usize>();
     for i in 0..(data.len() - 1) {
-        let r_index = roulette_wheel_selection(&data, i, max_weight_limit, &rng);
+        let r_index = if remaining_weight == 0 {
+            rng.usize(i..data.len())
+        } else {
+            roulette_wheel_selection(&data, i, max_weight_limit, rng)
+        };
+        remaining_weight -= data[r_index];
         data.swap(i, r_index);
         order.swap(i, r_index);
     }
@@ -246,6 +608,8 @@ fn prioritized_left_shuffling(mut data: Vec<usize>, max_weight_limit: usize) ->
 }
 
 /// Roulette wheel selection.
+///
+/// At least one weight in `data[start..]` must be positive.
 #[inline(always)]
 fn roulette_wheel_selection(
     data: &[usize],
@@ -266,6 +630,19 @@ fn roulette_wheel_selection(
     }
 }
 
+/// Derive the rng seed of an agent in a generation from the global seed.
+///
+/// Seeds are mixed with splitmix64 so that nearby agents get unrelated streams.
+fn agent_seed(seed: u64, generation: usize, agent: usize) -> u64 {
+    fn splitmix64(mut z: u64) -> u64 {
+        z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    splitmix64(splitmix64(seed ^ splitmix64(generation as u64)) ^ agent as u64)
+}
+
 #[cfg(test)]
 mod test_shuffle {
     use super::*;
@@ -275,8 +652,9 @@ mod test_shuffle {
         let data = vec![10usize, 90, 500, 1000, 9, 8, 7, 6, 5];
         let mut count_pos_4_at_1 = 0;
         let mut count_pos_3_at_2 = 0;
+        let rng = Rng::with_seed(42);
         for _ in 0..1000 {
-            let r = prioritized_left_shuffling(data.clone(), 1000);
+            let r = prioritized_left_shuffling(data.clone(), 1000, &rng);
             if r[0] == 3 {
                 count_pos_4_at_1 += 1;
             }
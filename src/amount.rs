@@ -0,0 +1,279 @@
+//! This file defines the fixed-point amount type used for balances, amounts and fees.
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+/// An exact fixed-point amount, stored as an integer number of minor units.
+///
+/// One unit equals `10^DECIMALS` minor units, so decimal strings like `"0.1"`
+/// are represented exactly, and `0.1 + 0.2 == 0.3` holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+impl Amount {
+    /// number of decimal places of one unit.
+    pub const DECIMALS: u32 = 8;
+    /// number of minor units in one unit.
+    pub const SCALE: i64 = 10i64.pow(Self::DECIMALS);
+    pub const ZERO: Amount = Amount(0);
+    pub const MAX: Amount = Amount(i64::MAX);
+
+    /// Construct an amount from a number of minor units.
+    pub const fn from_minor(minor: i64) -> Self {
+        Self(minor)
+    }
+
+    /// Construct an amount from a number of whole units.
+    ///
+    /// Panics on overflow.
+    pub const fn from_units(units: i64) -> Self {
+        match units.checked_mul(Self::SCALE) {
+            Some(minor) => Self(minor),
+            None => panic!("amount overflow"),
+        }
+    }
+
+    /// Get the number of minor units.
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    /// Panics on overflow, use `checked_add` for untrusted values.
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("amount overflow")
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    /// Panics on overflow, use `checked_sub` for untrusted values.
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect("amount overflow")
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    /// Parse a decimal string such as `"12"`, `"-0.5"` or `"90.0"`.
+    ///
+    /// Strings with more than `DECIMALS` significant decimal places
+    /// are rejected instead of being rounded.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid amount `{s}`");
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if (int_part.is_empty() && frac_part.is_empty())
+            || !int_part.bytes().all(|b| b.is_ascii_digit())
+            || !frac_part.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let frac_part = frac_part.trim_end_matches('0');
+        if frac_part.len() > Self::DECIMALS as usize {
+            return Err(format!(
+                "amount `{s}` has more than {} decimal places",
+                Self::DECIMALS
+            ));
+        }
+        let overflow = || format!("amount `{s}` is out of range");
+        let int_value = if int_part.is_empty() {
+            0
+        } else {
+            int_part.parse::<i64>().map_err(|_| overflow())?
+        };
+        let frac_value = if frac_part.is_empty() {
+            0
+        } else {
+            let padding = 10i64.pow(Self::DECIMALS - frac_part.len() as u32);
+            frac_part.parse::<i64>().map_err(|_| invalid())? * padding
+        };
+        let minor = int_value
+            .checked_mul(Self::SCALE)
+            .and_then(|v| v.checked_add(frac_value))
+            .ok_or_else(overflow)?;
+        Ok(Self(if negative { -minor } else { minor }))
+    }
+}
+
+impl Display for Amount {
+    /// Format as a decimal string without trailing zeros, which never loses precision.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        let (int_part, frac_part) = (abs / scale, abs % scale);
+        if frac_part == 0 {
+            write!(f, "{sign}{int_part}")
+        } else {
+            let frac = format!("{:0width$}", frac_part, width = Self::DECIMALS as usize);
+            write!(f, "{sign}{int_part}.{}", frac.trim_end_matches('0'))
+        }
+    }
+}
+
+impl Serialize for Amount {
+    /// Serialized as a decimal string to keep full precision.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Amount {
+    /// Deserialize from a decimal string only.
+    ///
+    /// Used for csv fields, which are always strings: letting csv infer a number
+    /// would read decimals through `f64` and lose precision.
+    pub(crate) fn deserialize_str<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(AmountVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    /// Deserialized from a decimal string or a number.
+    ///
+    /// Numbers are read through `f64`, so those with more than 15 significant digits
+    /// are rejected; use a string to pass such amounts exactly.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a decimal amount")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.trim().parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        v.checked_mul(Amount::SCALE)
+            .map(Amount)
+            .ok_or_else(|| E::custom(format!("amount `{v}` is out of range")))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        i64::try_from(v)
+            .map_err(|_| E::custom(format!("amount `{v}` is out of range")))
+            .and_then(|v| self.visit_i64(v))
+    }
+
+    /// Floats are parsed from their shortest decimal representation,
+    /// so that `0.1` is read as exactly `0.1`.
+    ///
+    /// Any decimal with at most 15 significant digits survives the round trip
+    /// through `f64`, longer ones may not and are rejected.
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        if !v.is_finite() {
+            return Err(E::custom(format!("invalid amount `{v}`")));
+        }
+        let s = v.to_string();
+        let significant = s.trim_start_matches(['-', '0', '.']).replace('.', "");
+        if significant.len() > 15 {
+            return Err(E::custom(format!(
+                "amount `{s}` has too many digits to be read exactly from a number, use a string"
+            )));
+        }
+        self.visit_str(&s)
+    }
+}
+
+#[cfg(test)]
+mod test_amount {
+    use super::*;
+
+    #[test]
+    fn test_exact_sum() {
+        let a: Amount = "0.1".parse().unwrap();
+        let b: Amount = "0.2".parse().unwrap();
+        assert_eq!(a + b, "0.3".parse().unwrap());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for (s, expected) in [
+            ("90.0", "90"),
+            ("0.5", "0.5"),
+            (".25", "0.25"),
+            ("-1.10", "-1.1"),
+            ("0.00000001", "0.00000001"),
+            ("12", "12"),
+        ] {
+            assert_eq!(s.parse::<Amount>().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for s in [
+            "",
+            ".",
+            "abc",
+            "1.2.3",
+            "1e5",
+            "0.000000001",
+            "99999999999999",
+        ] {
+            assert!(s.parse::<Amount>().is_err(), "{s} should not parse");
+        }
+    }
+}
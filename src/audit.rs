@@ -0,0 +1,127 @@
+//! This file defines the invariants checked on every solution in audit mode:
+//! value is only moved between addresses, and the system only gains the fees.
+use crate::{Address, Amount, Asset, Balance, Solution, SYSTEM_ADDRESS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// An invariant broken by the balance after executing a solution.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// the total supply of an asset, summed over every address including the system, changed.
+    SupplyChanged { asset: Asset, change: Amount },
+    /// a balance is negative.
+    NegativeBalance {
+        address: Address,
+        asset: Asset,
+        amount: Amount,
+    },
+    /// the system balance of an asset did not grow by the fees paid in this asset.
+    FeeMismatch {
+        asset: Asset,
+        system_gain: Amount,
+        fees: Amount,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SupplyChanged { asset, change } => {
+                write!(f, "total supply of {asset} changed by {change}")
+            }
+            Self::NegativeBalance {
+                address,
+                asset,
+                amount,
+            } => write!(f, "balance of {address} is negative: {amount} {asset}"),
+            Self::FeeMismatch {
+                asset,
+                system_gain,
+                fees,
+            } => write!(
+                f,
+                "system gained {system_gain} {asset} but the fees in {asset} sum to {fees}"
+            ),
+        }
+    }
+}
+
+/// Check the balance after executing a solution against the balance before,
+/// returning the broken invariants in a deterministic order.
+///
+/// Sums are computed in 128 bits, so that they cannot overflow.
+pub(crate) fn audit(before: &Balance, after: &Balance, solution: &Solution) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let mut supply_change: HashMap<&Asset, i128> = HashMap::new();
+    for ((_, asset), amount) in before {
+        *supply_change.entry(asset).or_default() -= amount.minor() as i128;
+    }
+    for ((_, asset), amount) in after {
+        *supply_change.entry(asset).or_default() += amount.minor() as i128;
+    }
+    let mut supply_change = supply_change
+        .into_iter()
+        .filter(|(_, change)| *change != 0)
+        .collect::<Vec<_>>();
+    supply_change.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+    violations.extend(
+        supply_change
+            .into_iter()
+            .map(|(asset, change)| Violation::SupplyChanged {
+                asset: asset.clone(),
+                change: clamp(change),
+            }),
+    );
+
+    let mut negative = after
+        .iter()
+        .filter(|(_, amount)| amount.is_negative())
+        .collect::<Vec<_>>();
+    negative.sort_by(|((a, x), _), ((b, y), _)| (&a.0, &x.0).cmp(&(&b.0, &y.0)));
+    violations.extend(negative.into_iter().map(|((address, asset), amount)| {
+        Violation::NegativeBalance {
+            address: address.clone(),
+            asset: asset.clone(),
+            amount: *amount,
+        }
+    }));
+
+    let mut fees: HashMap<&Asset, i128> = HashMap::new();
+    for tx in solution.transactions() {
+        *fees.entry(&tx.fee_asset).or_default() += tx.fee.minor() as i128;
+    }
+    let system_balance = |balance: &Balance, asset: &Asset| {
+        balance
+            .get(&(SYSTEM_ADDRESS.clone(), asset.clone()))
+            .map_or(0, |b| b.minor() as i128)
+    };
+    let mut assets = before
+        .keys()
+        .chain(after.keys())
+        .filter(|(address, _)| *address == *SYSTEM_ADDRESS)
+        .map(|(_, asset)| asset)
+        .chain(fees.keys().copied())
+        .collect::<Vec<_>>();
+    assets.sort_by(|a, b| a.0.cmp(&b.0));
+    assets.dedup();
+    for asset in assets {
+        let system_gain = system_balance(after, asset) - system_balance(before, asset);
+        let fees = fees.get(asset).copied().unwrap_or(0);
+        if system_gain != fees {
+            violations.push(Violation::FeeMismatch {
+                asset: asset.clone(),
+                system_gain: clamp(system_gain),
+                fees: clamp(fees),
+            });
+        }
+    }
+    violations
+}
+
+/// Convert a sum in minor units to an amount, saturating on overflow.
+fn clamp(minor: i128) -> Amount {
+    Amount::from_minor(minor.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
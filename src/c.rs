@@ -1,24 +1,151 @@
-///! This file define the C interface for the fee maximizer.
-use crate::{Address, FeeMaximizer, Request, Transaction};
+//! This file define the C interface for the fee maximizer.
+use crate::{
+    solver_from_name, Address, Amount, Asset, BlockCapacity, CancellationToken, FeeMaximizer,
+    FeeMaximizerError, Progress, ProgressCallback, Request, SelectionCurve, Solution, SolverConfig,
+    StopReason, Transaction, NATIVE_ASSET,
+};
 use libc::size_t;
-use std::ffi::{c_char, c_double, CString};
+use std::cell::Cell;
+use std::ffi::{c_char, c_void};
 use std::ffi::{c_int, CStr};
-use std::fmt::Display;
 use std::ptr::null_mut;
+use std::time::Duration;
+
+/// Error codes returned by the C interface, 0 means success.
+pub const FEE_MAXIMIZER_OK: c_int = 0;
+pub const FEE_MAXIMIZER_ERROR_EMPTY_ADDRESS: c_int = 1;
+pub const FEE_MAXIMIZER_ERROR_EMPTY_ASSET: c_int = 2;
+pub const FEE_MAXIMIZER_ERROR_SYSTEM_ADDRESS: c_int = 3;
+pub const FEE_MAXIMIZER_ERROR_NEGATIVE_AMOUNT: c_int = 4;
+pub const FEE_MAXIMIZER_ERROR_NEGATIVE_BALANCE: c_int = 5;
+pub const FEE_MAXIMIZER_ERROR_AMOUNT_OVERFLOW: c_int = 6;
+pub const FEE_MAXIMIZER_ERROR_INVALID_UTF8: c_int = 7;
+pub const FEE_MAXIMIZER_ERROR_INVALID_SOLVER_PARAMS: c_int = 8;
+pub const FEE_MAXIMIZER_ERROR_UNKNOWN_SOLVER: c_int = 9;
+pub const FEE_MAXIMIZER_ERROR_REQUEST_INDEX_OUT_OF_RANGE: c_int = 10;
+pub const FEE_MAXIMIZER_ERROR_SELF_CONFLICT: c_int = 11;
+pub const FEE_MAXIMIZER_ERROR_CYCLIC_DEPENDENCY: c_int = 12;
+pub const FEE_MAXIMIZER_ERROR_CAPACITY_EXCEEDED: c_int = 13;
+pub const FEE_MAXIMIZER_ERROR_INVALID_SOLUTION: c_int = 14;
+pub const FEE_MAXIMIZER_ERROR_CSV: c_int = 15;
+pub const FEE_MAXIMIZER_ERROR_IO: c_int = 16;
+pub const FEE_MAXIMIZER_ERROR_SOLVER: c_int = 17;
+pub const FEE_MAXIMIZER_ERROR_JSON: c_int = 18;
+pub const FEE_MAXIMIZER_ERROR_INSUFFICIENT_BALANCE: c_int = 19;
+pub const FEE_MAXIMIZER_ERROR_UNKNOWN_TRANSACTION: c_int = 20;
+pub const FEE_MAXIMIZER_ERROR_CONSTRAINT_VIOLATED: c_int = 21;
+pub const FEE_MAXIMIZER_ERROR_FEE_MISMATCH: c_int = 22;
+// codes reserved for the mempool, which has no C API yet, so they are not exported.
+const FEE_MAXIMIZER_ERROR_MISSING_REQUEST_ID: c_int = 23;
+const FEE_MAXIMIZER_ERROR_DUPLICATE_REQUEST_ID: c_int = 24;
+const FEE_MAXIMIZER_ERROR_UNKNOWN_REQUEST_ID: c_int = 25;
+
+/// Why the last solve stopped searching, see `fee_maximizer_last_stop_reason`.
+pub const FEE_MAXIMIZER_STOP_NONE: c_int = -1;
+pub const FEE_MAXIMIZER_STOP_COMPLETED: c_int = 0;
+pub const FEE_MAXIMIZER_STOP_TIME_BUDGET: c_int = 1;
+pub const FEE_MAXIMIZER_STOP_CONVERGED: c_int = 2;
+pub const FEE_MAXIMIZER_STOP_CANCELLED: c_int = 3;
+
+thread_local! {
+    static LAST_ERROR_CODE: Cell<c_int> = const { Cell::new(FEE_MAXIMIZER_OK) };
+}
 
 #[repr(C)]
 pub struct CSolution {
     pub txs: *mut CTransaction,
     pub n_txs: size_t,
     pub n_capacity: size_t,
+    /// provenance of each transaction in `txs` (`n_txs` entries).
+    pub provenance: *mut CTransactionRef,
+    /// transactions that are not executed, ordered by request and position.
+    pub rejected: *mut CTransactionRef,
+    pub n_rejected: size_t,
+    /// indices of the requests none of whose transactions are executed.
+    pub rejected_requests: *mut size_t,
+    pub n_rejected_requests: size_t,
+    /// total fee collected in the objective asset, in minor units.
+    pub total_fee: i64,
+    /// number of executed transactions counted against the block capacity.
+    pub used_transactions: size_t,
+    /// total weight of the executed transactions.
+    pub used_weight: u64,
+    /// messages of the invariants broken by the solution in audit mode
+    /// (see `fee_maximizer_set_audit`), in which case the balance is unchanged.
+    pub violations: *mut *const c_char,
+    pub n_violations: size_t,
+}
+
+/// Refers to a transaction by its request and its position within the request.
+#[repr(C)]
+pub struct CTransactionRef {
+    /// index of the request (as returned by `fee_maximizer_add_request`).
+    pub request: size_t,
+    /// position of the transaction within the request.
+    pub index: size_t,
+    /// whether `request_id` is set.
+    pub has_request_id: bool,
+    /// user-supplied id of the request (see `request_set_id`).
+    pub request_id: u64,
+}
+
+/// The balance of an address in an asset.
+#[repr(C)]
+pub struct CBalance {
+    pub address: *const c_char,
+    pub asset: *const c_char,
+    /// balance in minor units.
+    pub amount: i64,
 }
 
+/// A snapshot of every balance, sorted by address and asset.
+#[repr(C)]
+pub struct CBalances {
+    pub balances: *mut CBalance,
+    pub n_balances: size_t,
+}
+
+/// The progress of the genetic solver, reported after every generation.
+#[repr(C)]
+pub struct CProgress {
+    /// index of the generation, starting at 0.
+    pub generation: size_t,
+    /// best fee in the objective asset found so far, in minor units.
+    pub best_fee: i64,
+    /// mean fee of the feasible solutions of the generation, in minor units.
+    pub mean_fee: i64,
+    /// fraction of the solutions of the generation that are feasible.
+    pub feasible_fraction: f64,
+}
+
+/// The user data pointer passed back to a C progress callback.
+struct UserData(*mut c_void);
+
+// Safety: the callback is only invoked on the thread calling the solver,
+// it is up to the caller to make the user data usable there.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Amounts and fees are integers in minor units
+/// (one unit is 10^8 minor units).
 #[repr(C)]
 pub struct CTransaction {
     pub from: *const c_char,
     pub to: *const c_char,
-    pub amount: c_double,
-    pub fee: c_double,
+    pub amount: i64,
+    pub fee: i64,
+    /// asset of `amount`, the native asset if null.
+    pub asset: *const c_char,
+    /// asset of `fee`, the native asset if null.
+    pub fee_asset: *const c_char,
+    /// resource consumed in a block (e.g., gas or byte size).
+    pub weight: u64,
 }
 
 /// Request constructor
@@ -29,7 +156,7 @@ pub extern "C" fn request_init() -> *mut Request {
 
 /// Adds transaction to Request.
 ///
-/// Return 0 if success, 1 if error.
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
 ///
 /// The error string is allocated using `malloc` on error and
 /// must be freed by the caller using `libc::free`.
@@ -42,13 +169,23 @@ pub unsafe extern "C" fn request_add_transaction(
     let req = &mut *req;
     match req.add_transaction(Transaction::from_c(tx)) {
         Ok(_) => 0,
-        Err(m) => {
-            write_error_c_str(m, error);
-            1
-        }
+        Err(e) => write_error_c_str(e, error),
     }
 }
 
+/// Set whether the request is atomic,
+/// i.e., its transactions are either all executed or none executed.
+#[no_mangle]
+pub unsafe extern "C" fn request_set_atomic(req: *mut Request, atomic: bool) {
+    (*req).set_atomic(atomic);
+}
+
+/// Set a user-supplied id of the request, reported in the solution.
+#[no_mangle]
+pub unsafe extern "C" fn request_set_id(req: *mut Request, id: u64) {
+    (*req).set_id(id);
+}
+
 /// Request destructor.
 ///
 /// # Safety
@@ -68,7 +205,7 @@ pub extern "C" fn fee_maximizer_init() -> *mut FeeMaximizer {
 ///
 /// This function add balance from a csv file with two columns (User and balance).
 ///
-/// Return 0 if success, 1 if error.
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
 ///
 /// The error string is allocated using `malloc` on error and
 /// must be freed by the caller using `libc::free`.
@@ -81,21 +218,89 @@ pub unsafe extern "C" fn fee_maximizer_add_balance_from_csv(
 ) -> c_int {
     let balance_csv = match CStr::from_ptr(balance_csv).to_str() {
         Ok(csv) => csv,
-        Err(e) => {
-            write_error_c_str(e, error);
-            return 1;
-        }
+        Err(_) => return write_error_c_str(FeeMaximizerError::InvalidUtf8, error),
     };
     match (*maximizer).add_balance_from_csv(balance_csv, header) {
         Ok(_) => 0,
-        Err(e) => {
-            write_error_c_str(e, error);
-            1
-        }
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Fee maximizer add requests.
+///
+/// This function add requests from a csv file with columns request, from, to,
+/// amount and fee, and the optional columns atomic, weight, asset and fee_asset.
+/// The `request` column is used as request id, and requests are added
+/// in the order of their first row.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_requests_from_csv(
+    maximizer: *mut FeeMaximizer,
+    requests_csv: *const c_char,
+    header: bool,
+    error: *mut *mut c_char,
+) -> c_int {
+    let requests_csv = match CStr::from_ptr(requests_csv).to_str() {
+        Ok(csv) => csv,
+        Err(_) => return write_error_c_str(FeeMaximizerError::InvalidUtf8, error),
+    };
+    match (*maximizer).add_requests_from_csv(requests_csv, header) {
+        Ok(_) => 0,
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Fee maximizer add balance from an in-memory csv buffer.
+///
+/// Same as `fee_maximizer_add_balance_from_csv`, but reads the csv from
+/// the `len` bytes at `data` instead of a file. `data` may be null if `len` is 0.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_balance_from_csv_buffer(
+    maximizer: *mut FeeMaximizer,
+    data: *const u8,
+    len: size_t,
+    header: bool,
+    error: *mut *mut c_char,
+) -> c_int {
+    match (*maximizer).add_balance_from_reader(bytes_from_c(data, len), header) {
+        Ok(_) => 0,
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Fee maximizer add requests from an in-memory csv buffer.
+///
+/// Same as `fee_maximizer_add_requests_from_csv`, but reads the csv from
+/// the `len` bytes at `data` instead of a file. `data` may be null if `len` is 0.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_requests_from_csv_buffer(
+    maximizer: *mut FeeMaximizer,
+    data: *const u8,
+    len: size_t,
+    header: bool,
+    error: *mut *mut c_char,
+) -> c_int {
+    match (*maximizer).add_requests_from_reader(bytes_from_c(data, len), header) {
+        Ok(_) => 0,
+        Err(e) => write_error_c_str(e, error),
     }
 }
 
-/// Add a request to fee maximizer.
+/// Add a request to fee maximizer, and return the index of the request.
 ///
 /// Note: this function only borrows request,
 /// so it is safe to free request after calling this function.
@@ -103,8 +308,56 @@ pub unsafe extern "C" fn fee_maximizer_add_balance_from_csv(
 pub unsafe extern "C" fn fee_maximizer_add_request(
     maximizer: *mut FeeMaximizer,
     req: *const Request,
+) -> size_t {
+    (*maximizer).add_request(&*req)
+}
+
+/// Request `request` may only be included if request `depends_on`
+/// is included and executed before it.
+///
+/// Requests are referred to by the index returned by `fee_maximizer_add_request`.
+/// Out of range indices and cyclic dependencies are reported when solving.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_dependency(
+    maximizer: *mut FeeMaximizer,
+    request: size_t,
+    depends_on: size_t,
+) {
+    (*maximizer).add_dependency(request, depends_on);
+}
+
+/// Requests `a` and `b` cannot be both included.
+///
+/// Requests are referred to by the index returned by `fee_maximizer_add_request`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_conflict(
+    maximizer: *mut FeeMaximizer,
+    a: size_t,
+    b: size_t,
+) {
+    (*maximizer).add_conflict(a, b);
+}
+
+/// Limit the number of transactions and the total weight of the solution.
+///
+/// A limit of 0 means no limit.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_set_block_capacity(
+    maximizer: *mut FeeMaximizer,
+    max_transactions: size_t,
+    max_weight: u64,
 ) {
-    (*maximizer).add_request(&*req);
+    (*maximizer).set_block_capacity(BlockCapacity {
+        max_transactions: (max_transactions > 0).then_some(max_transactions),
+        max_weight: (max_weight > 0).then_some(max_weight),
+    });
+}
+
+/// Check the invariants of every solution (on by default in debug builds):
+/// broken invariants are reported in the `violations` of the solution.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_set_audit(maximizer: *mut FeeMaximizer, audit: bool) {
+    (*maximizer).set_audit(audit);
 }
 
 /// Fee maximizer solve and get result.
@@ -114,7 +367,8 @@ pub unsafe extern "C" fn fee_maximizer_add_request(
 /// - selection_size: number of individuals selected for the next generation.
 /// - max_generation: maximum number of generations.
 ///
-/// The returned pointer is `nullptr` if an error occurs.
+/// The returned pointer is `nullptr` if an error occurs,
+/// and the error code is given by `fee_maximizer_last_error_code`.
 ///
 /// The error string is allocated using `malloc` on error and
 /// must be freed by the caller using `libc::free`.
@@ -133,34 +387,424 @@ pub unsafe extern "C" fn fee_maximizer_solve(
             return null_mut();
         }
     };
-    let mut txs = sol
-        .iter()
-        .map(|tx| tx.to_c())
-        .collect::<Vec<CTransaction>>();
-    let sol = CSolution {
-        txs: txs.as_mut_ptr(),
-        n_txs: txs.len(),
-        n_capacity: txs.capacity(),
+    CSolution::from_solution(sol)
+}
+
+/// Solver config constructor, initialized with the default parameters.
+#[no_mangle]
+pub extern "C" fn solver_config_init() -> *mut SolverConfig {
+    Box::into_raw(Box::default())
+}
+
+/// Set the genetic algorithm parameters of a solver config.
+///
+/// - population_size: number of individuals in the population.
+/// - selection_size: number of individuals selected for the next generation.
+/// - max_generation: maximum number of generations.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_genetic_params(
+    config: *mut SolverConfig,
+    population_size: size_t,
+    selection_size: size_t,
+    max_generation: size_t,
+) {
+    let config = &mut *config;
+    config.population_size = population_size;
+    config.selection_size = selection_size;
+    config.num_generation = max_generation;
+}
+
+/// Set the rng seed of a solver config, so that solving is reproducible.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_seed(config: *mut SolverConfig, seed: u64) {
+    (*config).seed = Some(seed);
+}
+
+/// Limit the wall-clock time of the genetic solver, in milliseconds.
+///
+/// The best solution found so far is returned when the time runs out.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_time_budget(config: *mut SolverConfig, millis: u64) {
+    (*config).time_budget = Some(Duration::from_millis(millis));
+}
+
+/// Stop the genetic solver after `patience` generations without improvement of the best fee.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_patience(config: *mut SolverConfig, patience: size_t) {
+    (*config).patience = Some(patience);
+}
+
+/// Set the selection curve of the genetic solver from its name:
+/// `linear`, `sigmoid`, `sigmoid:<steepness>`, `power` or `power:<exponent>`.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_selection_curve(
+    config: *mut SolverConfig,
+    curve: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    let curve = CStr::from_ptr(curve)
+        .to_str()
+        .map_err(|_| FeeMaximizerError::InvalidUtf8)
+        .and_then(|curve| {
+            curve
+                .parse()
+                .map_err(FeeMaximizerError::InvalidSolverParams)
+        });
+    match curve {
+        Ok(curve) => {
+            (*config).genetic.curve = curve;
+            0
+        }
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Use `curve(weight, user_data)` as the selection curve of the genetic solver,
+/// mapping a weight between 0 and 1 to a selection probability.
+///
+/// The curve is called concurrently from the solver threads.
+/// Pass a null curve to go back to the linear curve.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_custom_selection_curve(
+    config: *mut SolverConfig,
+    curve: Option<unsafe extern "C" fn(weight: f64, user_data: *mut c_void) -> f64>,
+    user_data: *mut c_void,
+) {
+    (*config).genetic.curve = match curve {
+        Some(curve) => {
+            let user_data = UserData(user_data);
+            SelectionCurve::custom(move |weight| unsafe { curve(weight, user_data.get()) })
+        }
+        None => SelectionCurve::Linear,
+    };
+}
+
+/// Set the minimum probability of selecting, and of dropping,
+/// any transaction in the genetic solver, between 0 and 0.5.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_mutation_rate(
+    config: *mut SolverConfig,
+    mutation_rate: f64,
+) {
+    (*config).genetic.mutation_rate = mutation_rate;
+}
+
+/// Set the probabilities, between 0 and 1, that an agent of the genetic solver
+/// ignores the learned weights to select transactions, and to order requests.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_exploration(
+    config: *mut SolverConfig,
+    selection: f64,
+    order: f64,
+) {
+    (*config).genetic.selection_exploration = selection;
+    (*config).genetic.order_exploration = order;
+}
+
+/// Call `callback` with the progress and `user_data` after every generation
+/// of the genetic solver, on the thread calling `fee_maximizer_solve_with`.
+///
+/// The progress pointer is only valid during the call. Pass a null callback to remove it.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_progress_callback(
+    config: *mut SolverConfig,
+    callback: Option<unsafe extern "C" fn(progress: *const CProgress, user_data: *mut c_void)>,
+    user_data: *mut c_void,
+) {
+    (*config).progress = callback.map(|callback| {
+        let user_data = UserData(user_data);
+        ProgressCallback::new(move |progress: &Progress| {
+            let progress = CProgress {
+                generation: progress.generation,
+                best_fee: progress.best_fee.minor(),
+                mean_fee: progress.mean_fee.minor(),
+                feasible_fraction: progress.feasible_fraction,
+            };
+            unsafe { callback(&progress, user_data.get()) }
+        })
+    });
+}
+
+/// Stop the genetic solver when `token` is cancelled.
+///
+/// The config shares the token, so it is safe to destroy the token after this call,
+/// and cancelling any handle to it stops the solves using this config.
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_set_cancellation_token(
+    config: *mut SolverConfig,
+    token: *const CancellationToken,
+) {
+    (*config).cancellation = Some((*token).clone());
+}
+
+/// Cancellation token constructor.
+#[no_mangle]
+pub extern "C" fn cancellation_token_init() -> *mut CancellationToken {
+    Box::into_raw(Box::new(CancellationToken::new()))
+}
+
+/// Cancel a token, e.g. from another thread or from a progress callback.
+///
+/// The solvers using it stop before their next generation,
+/// and return the best solution found so far.
+#[no_mangle]
+pub unsafe extern "C" fn cancellation_token_cancel(token: *const CancellationToken) {
+    (*token).cancel();
+}
+
+/// Cancellation token destructor.
+///
+/// Safety: do never double call!
+#[no_mangle]
+pub unsafe extern "C" fn cancellation_token_destroy(token: *mut CancellationToken) {
+    drop(Box::from_raw(token))
+}
+
+/// Solver config destructor.
+///
+/// Safety: do never double call!
+#[no_mangle]
+pub unsafe extern "C" fn solver_config_destroy(config: *mut SolverConfig) {
+    drop(Box::from_raw(config))
+}
+
+/// Fee maximizer solve with a solver selected by name, and get result.
+///
+/// Available solvers: "genetic", "greedy", "exact".
+///
+/// Note: this function only borrows config,
+/// so it is safe to free config after calling this function.
+///
+/// The returned pointer is `nullptr` if an error occurs,
+/// and the error code is given by `fee_maximizer_last_error_code`.
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_solve_with(
+    maximizer: *mut FeeMaximizer,
+    solver_name: *const c_char,
+    config: *const SolverConfig,
+    error: *mut *mut c_char,
+) -> *mut CSolution {
+    let solver_name = match CStr::from_ptr(solver_name).to_str() {
+        Ok(name) => name,
+        Err(_) => {
+            write_error_c_str(FeeMaximizerError::InvalidUtf8, error);
+            return null_mut();
+        }
+    };
+    let solver = match solver_from_name(solver_name, (*config).clone()) {
+        Ok(solver) => solver,
+        Err(e) => {
+            write_error_c_str(e, error);
+            return null_mut();
+        }
     };
-    // prevent `txs` from being dropped
-    std::mem::forget(txs);
-    Box::into_raw(Box::new(sol))
+    match (*maximizer).solve_with(solver.as_ref()) {
+        Ok(sol) => CSolution::from_solution(sol),
+        Err(e) => {
+            write_error_c_str(e, error);
+            null_mut()
+        }
+    }
+}
+
+/// Get why the last successful solve stopped searching (see `FEE_MAXIMIZER_STOP_*`),
+/// or `FEE_MAXIMIZER_STOP_NONE` if nothing was solved yet.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_last_stop_reason(maximizer: *const FeeMaximizer) -> c_int {
+    match (*maximizer).stats().map(|s| s.stop_reason) {
+        None => FEE_MAXIMIZER_STOP_NONE,
+        Some(StopReason::Completed) => FEE_MAXIMIZER_STOP_COMPLETED,
+        Some(StopReason::TimeBudget) => FEE_MAXIMIZER_STOP_TIME_BUDGET,
+        Some(StopReason::Converged) => FEE_MAXIMIZER_STOP_CONVERGED,
+        Some(StopReason::Cancelled) => FEE_MAXIMIZER_STOP_CANCELLED,
+    }
+}
+
+/// Get the number of generations run by the last successful solve,
+/// 0 if nothing was solved yet or the solver is not genetic.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_last_generations(maximizer: *const FeeMaximizer) -> size_t {
+    (*maximizer).stats().map_or(0, |s| s.generations)
 }
 
-/// Query address balance.
+/// Get the total fee of the last successful solve in the objective asset,
+/// in minor units, 0 if nothing was solved yet.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_last_best_fee(maximizer: *const FeeMaximizer) -> i64 {
+    (*maximizer).stats().map_or(0, |s| s.best_fee.minor())
+}
+
+/// Maximize the fees paid in `asset` (the native asset by default).
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
 ///
-/// Return -200.0 when address is not a valid UTF-8 string.
-/// Return -1.0 when address not found.
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_set_objective_asset(
+    maximizer: *mut FeeMaximizer,
+    asset: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    let asset = CStr::from_ptr(asset).to_owned().into_bytes();
+    if asset.is_empty() {
+        return write_error_c_str(FeeMaximizerError::EmptyAsset, error);
+    }
+    (*maximizer).set_objective_asset(Asset(asset));
+    0
+}
+
+/// Query address balance of the native asset in minor units.
+///
+/// Return -200 when address is not a valid UTF-8 string.
+/// Return -1 when address not found.
 #[no_mangle]
 pub unsafe extern "C" fn fee_maximizer_query_address_balance(
     maximizer: *mut FeeMaximizer,
     address: *const c_char,
-) -> c_double {
+) -> i64 {
     let address = match CStr::from_ptr(address).to_str() {
         Ok(address) => address,
-        Err(_) => return -200.0,
+        Err(_) => return -200,
     };
-    (*maximizer).get_balance(&Address(address.as_bytes().to_vec()))
+    (*maximizer)
+        .get_balance(&Address(address.as_bytes().to_vec()))
+        .minor()
+}
+
+/// Query address balance of an asset in minor units.
+///
+/// Return -200 when address or asset is not a valid UTF-8 string.
+/// Return -1 when the address does not hold the asset.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_query_address_asset_balance(
+    maximizer: *mut FeeMaximizer,
+    address: *const c_char,
+    asset: *const c_char,
+) -> i64 {
+    let (address, asset) = match (
+        CStr::from_ptr(address).to_str(),
+        CStr::from_ptr(asset).to_str(),
+    ) {
+        (Ok(address), Ok(asset)) => (address, asset),
+        _ => return -200,
+    };
+    (*maximizer)
+        .get_asset_balance(
+            &Address(address.as_bytes().to_vec()),
+            &Asset(asset.as_bytes().to_vec()),
+        )
+        .minor()
+}
+
+/// Set the balance of an address in an asset (the native asset if null or empty)
+/// in minor units, replacing any previous balance.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_set_balance(
+    maximizer: *mut FeeMaximizer,
+    address: *const c_char,
+    asset: *const c_char,
+    amount: i64,
+    error: *mut *mut c_char,
+) -> c_int {
+    let result = Address::from_c(address).and_then(|address| {
+        (*maximizer).set_balance(address, Asset::from_c(asset), Amount::from_minor(amount))
+    });
+    match result {
+        Ok(_) => 0,
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Add to the balance of an address in an asset (the native asset if null or empty)
+/// in minor units, like every row of a balance csv.
+///
+/// Return 0 if success, or an error code (see `FEE_MAXIMIZER_ERROR_*`).
+///
+/// The error string is allocated using `malloc` on error and
+/// must be freed by the caller using `libc::free`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_add_balance(
+    maximizer: *mut FeeMaximizer,
+    address: *const c_char,
+    asset: *const c_char,
+    amount: i64,
+    error: *mut *mut c_char,
+) -> c_int {
+    let result = Address::from_c(address).and_then(|address| {
+        (*maximizer).add_balance(address, Asset::from_c(asset), Amount::from_minor(amount))
+    });
+    match result {
+        Ok(_) => 0,
+        Err(e) => write_error_c_str(e, error),
+    }
+}
+
+/// Remove the balance of an address in an asset (the native asset if null or empty).
+///
+/// Return the removed balance in minor units, or -1 when the address does not hold the asset.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_remove_balance(
+    maximizer: *mut FeeMaximizer,
+    address: *const c_char,
+    asset: *const c_char,
+) -> i64 {
+    let address = Address(CStr::from_ptr(address).to_bytes().to_vec());
+    (*maximizer)
+        .remove_balance(&address, &Asset::from_c(asset))
+        .unwrap_or(Amount::from_minor(-1))
+        .minor()
+}
+
+/// Get a snapshot of every balance, sorted by address and asset,
+/// e.g. to read the final balances after solving.
+///
+/// The snapshot does not change with the fee maximizer,
+/// and must be freed with `balances_destroy`.
+#[no_mangle]
+pub unsafe extern "C" fn fee_maximizer_balances(maximizer: *const FeeMaximizer) -> *mut CBalances {
+    let balances = (*maximizer)
+        .sorted_balance()
+        .into_iter()
+        .map(|((address, asset), amount)| CBalance {
+            address: bytes_to_c(&address.0),
+            asset: bytes_to_c(&asset.0),
+            amount: amount.minor(),
+        })
+        .collect::<Vec<_>>();
+    Box::into_raw(Box::new(CBalances {
+        n_balances: balances.len(),
+        balances: boxed_slice_into_raw(balances),
+    }))
+}
+
+/// Balance snapshot destructor.
+///
+/// Safety: do never double call!
+#[no_mangle]
+pub unsafe extern "C" fn balances_destroy(balances: *mut CBalances) {
+    if balances.is_null() {
+        return;
+    }
+    let balances = Box::from_raw(balances);
+    // free memory allocated for `address` and `asset` fields
+    for balance in boxed_slice_from_raw(balances.balances, balances.n_balances).iter() {
+        libc::free(balance.address as *mut libc::c_void);
+        libc::free(balance.asset as *mut libc::c_void);
+    }
 }
 
 /// Solution destructor.
@@ -171,18 +815,34 @@ pub unsafe extern "C" fn solution_destroy(sol: *mut CSolution) {
     if sol.is_null() {
         return;
     }
-    let txs = Vec::from_raw_parts(
-        (*sol).txs as *mut CTransaction,
-        (*sol).n_txs,
-        (*sol).n_capacity,
-    );
-    // free memory allocated for `from` and `to` fields
+    let sol = Box::from_raw(sol);
+    let txs = Vec::from_raw_parts(sol.txs, sol.n_txs, sol.n_capacity);
+    // free memory allocated for `from`, `to`, `asset` and `fee_asset` fields
     for tx in txs.iter() {
         libc::free(tx.from as *mut libc::c_void);
         libc::free(tx.to as *mut libc::c_void);
+        libc::free(tx.asset as *mut libc::c_void);
+        libc::free(tx.fee_asset as *mut libc::c_void);
+    }
+    // free memory allocated for provenance and rejected lists
+    drop(boxed_slice_from_raw(sol.provenance, sol.n_txs));
+    drop(boxed_slice_from_raw(sol.rejected, sol.n_rejected));
+    drop(boxed_slice_from_raw(
+        sol.rejected_requests,
+        sol.n_rejected_requests,
+    ));
+    for violation in boxed_slice_from_raw(sol.violations, sol.n_violations).iter() {
+        libc::free(*violation as *mut libc::c_void);
     }
-    // free memory allocated for solution
-    drop(Box::from_raw(sol))
+}
+
+/// Get the error code of the last failed call on the current thread,
+/// or `FEE_MAXIMIZER_OK` if no call has failed.
+///
+/// Useful for functions returning `nullptr` on error.
+#[no_mangle]
+pub extern "C" fn fee_maximizer_last_error_code() -> c_int {
+    LAST_ERROR_CODE.with(|c| c.get())
 }
 
 /// Fee maximizer destructor.
@@ -193,9 +853,75 @@ pub unsafe extern "C" fn fee_maximizer_destroy(maximizer: *mut FeeMaximizer) {
     drop(Box::from_raw(maximizer))
 }
 
+impl CSolution {
+    /// Move a solution into a heap allocated `CSolution`.
+    fn from_solution(sol: Solution) -> *mut CSolution {
+        let mut txs = sol
+            .executed
+            .iter()
+            .map(|e| unsafe { e.transaction.to_c() })
+            .collect::<Vec<CTransaction>>();
+        let provenance = sol
+            .executed
+            .iter()
+            .map(|e| CTransactionRef::new(e.request, e.request_id, e.index))
+            .collect::<Vec<_>>();
+        let rejected = sol
+            .rejected
+            .iter()
+            .map(|r| CTransactionRef::new(r.request, r.request_id, r.index))
+            .collect::<Vec<_>>();
+        let violations = sol
+            .violations
+            .iter()
+            .map(|v| unsafe { bytes_to_c(v.to_string().as_bytes()) })
+            .collect::<Vec<_>>();
+        let c_sol = CSolution {
+            txs: txs.as_mut_ptr(),
+            n_txs: txs.len(),
+            n_capacity: txs.capacity(),
+            provenance: boxed_slice_into_raw(provenance),
+            n_rejected: rejected.len(),
+            rejected: boxed_slice_into_raw(rejected),
+            n_rejected_requests: sol.rejected_requests.len(),
+            rejected_requests: boxed_slice_into_raw(sol.rejected_requests),
+            total_fee: sol.total_fee.minor(),
+            used_transactions: sol.usage.transactions,
+            used_weight: sol.usage.weight,
+            n_violations: violations.len(),
+            violations: boxed_slice_into_raw(violations),
+        };
+        // prevent `txs` from being dropped
+        std::mem::forget(txs);
+        Box::into_raw(Box::new(c_sol))
+    }
+}
+
+impl CTransactionRef {
+    fn new(request: usize, request_id: Option<u64>, index: usize) -> Self {
+        Self {
+            request,
+            index,
+            has_request_id: request_id.is_some(),
+            request_id: request_id.unwrap_or(0),
+        }
+    }
+}
+
+/// Move a vector into a heap allocated array of exactly `v.len()` elements.
+fn boxed_slice_into_raw<T>(v: Vec<T>) -> *mut T {
+    Box::into_raw(v.into_boxed_slice()) as *mut T
+}
+
+/// Safety: `ptr` must be returned by `boxed_slice_into_raw` with `len` elements.
+unsafe fn boxed_slice_from_raw<T>(ptr: *mut T, len: usize) -> Box<[T]> {
+    Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len))
+}
+
 impl Transaction {
     /// Safety: must ensure that CTransaction struct contains valid
-    /// C strings in `from` and `to` fields (i.e., nul terminated valid string).
+    /// C strings in `from` and `to` fields (i.e., nul terminated valid string),
+    /// and valid C strings or null in `asset` and `fee_asset` fields.
     #[inline]
     unsafe fn from_c(tx: CTransaction) -> Self {
         let from_str = CStr::from_ptr(tx.from).to_owned().into_bytes();
@@ -203,35 +929,125 @@ impl Transaction {
         Self {
             from: Address(from_str),
             to: Address(to_str),
-            amount: tx.amount,
-            fee: tx.fee,
+            amount: Amount::from_minor(tx.amount),
+            fee: Amount::from_minor(tx.fee),
+            asset: Asset::from_c(tx.asset),
+            fee_asset: Asset::from_c(tx.fee_asset),
+            weight: tx.weight,
         }
     }
 
     /// Convert to CTransaction struct.
-    /// Use `malloc` to allocate memory for `from` and `to` fields.
+    /// Use `malloc` to allocate memory for `from`, `to`, `asset` and `fee_asset` fields.
     ///
-    /// Note: the caller must free the memory allocated for `from` and `to` fields.
+    /// Note: the caller must free the memory allocated for these fields.
     #[inline]
     unsafe fn to_c(&self) -> CTransaction {
-        let from = libc::malloc(self.from.0.len() + 1) as *mut c_char;
-        libc::strcpy(from, self.from.0.as_ptr() as *const c_char);
-        let to = libc::malloc(self.to.0.len() + 1) as *mut c_char;
-        libc::strcpy(to, self.to.0.as_ptr() as *const c_char);
         CTransaction {
-            from,
-            to,
-            amount: self.amount,
-            fee: self.fee,
+            from: bytes_to_c(&self.from.0),
+            to: bytes_to_c(&self.to.0),
+            amount: self.amount.minor(),
+            fee: self.fee.minor(),
+            asset: bytes_to_c(&self.asset.0),
+            fee_asset: bytes_to_c(&self.fee_asset.0),
+            weight: self.weight,
+        }
+    }
+}
+
+impl Address {
+    /// Read an address from a C string.
+    #[inline]
+    unsafe fn from_c(address: *const c_char) -> Result<Self, FeeMaximizerError> {
+        match CStr::from_ptr(address).to_bytes() {
+            [] => Err(FeeMaximizerError::EmptyAddress),
+            bytes => Ok(Address(bytes.to_vec())),
+        }
+    }
+}
+
+impl Asset {
+    /// Read an asset from a C string, the native asset if null or empty.
+    #[inline]
+    unsafe fn from_c(asset: *const c_char) -> Self {
+        if asset.is_null() {
+            return NATIVE_ASSET.clone();
+        }
+        match CStr::from_ptr(asset).to_bytes() {
+            [] => NATIVE_ASSET.clone(),
+            bytes => Asset(bytes.to_vec()),
         }
     }
 }
 
-/// Turn an error with `Display` into a C string pointer using `malloc`.
-fn write_error_c_str<E: Display>(e: E, error: *mut *mut c_char) {
-    let error_str = CString::new(format!("{}", e)).unwrap();
+/// Borrow `len` bytes at `data`, which may be null if `len` is 0.
+///
+/// Safety: `data` must point to at least `len` readable bytes.
+#[inline]
+unsafe fn bytes_from_c<'a>(data: *const u8, len: size_t) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    }
+}
+
+/// Copy bytes into a nul terminated C string allocated by `malloc`.
+///
+/// Note: the caller must free the returned memory.
+#[inline]
+unsafe fn bytes_to_c(bytes: &[u8]) -> *const c_char {
+    let len = bytes.len();
+    let ptr = libc::malloc(len + 1) as *mut u8;
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, len);
+    *ptr.add(len) = 0;
+    ptr as *const c_char
+}
+
+/// Turn an error into a C string pointer using `malloc`,
+/// record its code as the last error code of the thread and return it.
+///
+/// Interior NUL bytes (e.g. echoed from an input buffer) are escaped as `\0`,
+/// so that building the C string cannot fail.
+fn write_error_c_str(e: FeeMaximizerError, error: *mut *mut c_char) -> c_int {
+    let code = error_code(&e);
+    LAST_ERROR_CODE.with(|c| c.set(code));
+    let error_str = e.to_string().replace('\0', "\\0");
     unsafe {
-        *error = libc::malloc(error_str.as_bytes().len() + 1) as *mut c_char;
-        libc::strcpy(*error, error_str.as_ptr());
+        *error = bytes_to_c(error_str.as_bytes()) as *mut c_char;
+    }
+    code
+}
+
+/// The C error code of an error.
+fn error_code(e: &FeeMaximizerError) -> c_int {
+    match e {
+        FeeMaximizerError::EmptyAddress => FEE_MAXIMIZER_ERROR_EMPTY_ADDRESS,
+        FeeMaximizerError::EmptyAsset => FEE_MAXIMIZER_ERROR_EMPTY_ASSET,
+        FeeMaximizerError::SystemAddress => FEE_MAXIMIZER_ERROR_SYSTEM_ADDRESS,
+        FeeMaximizerError::NegativeAmount => FEE_MAXIMIZER_ERROR_NEGATIVE_AMOUNT,
+        FeeMaximizerError::NegativeBalance => FEE_MAXIMIZER_ERROR_NEGATIVE_BALANCE,
+        FeeMaximizerError::AmountOverflow => FEE_MAXIMIZER_ERROR_AMOUNT_OVERFLOW,
+        FeeMaximizerError::InvalidUtf8 => FEE_MAXIMIZER_ERROR_INVALID_UTF8,
+        FeeMaximizerError::InvalidSolverParams(_) => FEE_MAXIMIZER_ERROR_INVALID_SOLVER_PARAMS,
+        FeeMaximizerError::UnknownSolver(_) => FEE_MAXIMIZER_ERROR_UNKNOWN_SOLVER,
+        FeeMaximizerError::RequestIndexOutOfRange { .. } => {
+            FEE_MAXIMIZER_ERROR_REQUEST_INDEX_OUT_OF_RANGE
+        }
+        FeeMaximizerError::SelfConflict(_) => FEE_MAXIMIZER_ERROR_SELF_CONFLICT,
+        FeeMaximizerError::CyclicDependency(_) => FEE_MAXIMIZER_ERROR_CYCLIC_DEPENDENCY,
+        FeeMaximizerError::CapacityExceeded { .. } => FEE_MAXIMIZER_ERROR_CAPACITY_EXCEEDED,
+        FeeMaximizerError::InvalidSolution { .. } => FEE_MAXIMIZER_ERROR_INVALID_SOLUTION,
+        FeeMaximizerError::Csv { .. } => FEE_MAXIMIZER_ERROR_CSV,
+        FeeMaximizerError::Io(_) => FEE_MAXIMIZER_ERROR_IO,
+        FeeMaximizerError::Solver(_) => FEE_MAXIMIZER_ERROR_SOLVER,
+        FeeMaximizerError::Json(_) => FEE_MAXIMIZER_ERROR_JSON,
+        FeeMaximizerError::InsufficientBalance { .. } => FEE_MAXIMIZER_ERROR_INSUFFICIENT_BALANCE,
+        FeeMaximizerError::UnknownTransaction { .. } => FEE_MAXIMIZER_ERROR_UNKNOWN_TRANSACTION,
+        FeeMaximizerError::ConstraintViolated(_) => FEE_MAXIMIZER_ERROR_CONSTRAINT_VIOLATED,
+        FeeMaximizerError::FeeMismatch { .. } => FEE_MAXIMIZER_ERROR_FEE_MISMATCH,
+        FeeMaximizerError::MissingRequestId => FEE_MAXIMIZER_ERROR_MISSING_REQUEST_ID,
+        FeeMaximizerError::DuplicateRequestId(_) => FEE_MAXIMIZER_ERROR_DUPLICATE_REQUEST_ID,
+        FeeMaximizerError::UnknownRequestId(_) => FEE_MAXIMIZER_ERROR_UNKNOWN_REQUEST_ID,
     }
 }
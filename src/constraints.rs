@@ -0,0 +1,193 @@
+//! This file defines dependency and conflict constraints between requests,
+//! and the capacity of a block.
+use crate::{FeeMaximizerError, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Constraints between requests, referring to requests by their index
+/// (i.e., the order in which they are added to the optimizer).
+///
+/// A request is *included* if at least one of its transactions is executed.
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    /// (request, depends_on) pairs.
+    dependencies: Vec<(usize, usize)>,
+    /// pairs of mutually exclusive requests.
+    conflicts: Vec<(usize, usize)>,
+}
+
+/// The capacity of a block, unlimited by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockCapacity {
+    /// maximum number of transactions in the block.
+    pub max_transactions: Option<usize>,
+    /// maximum total weight of the transactions in the block.
+    pub max_weight: Option<u64>,
+}
+
+/// The capacity consumed by the selected transactions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockUsage {
+    pub transactions: usize,
+    pub weight: u64,
+}
+
+/// Adjacency lists built from validated constraints.
+pub(crate) struct RequestGraph {
+    /// requests that must be included and executed before each request.
+    pub(crate) depends_on: Vec<Vec<usize>>,
+    /// requests that depend on each request.
+    pub(crate) dependents: Vec<Vec<usize>>,
+    /// requests that cannot be included together with each request.
+    pub(crate) conflicts: Vec<Vec<usize>>,
+    /// a topological order of the requests (dependencies first).
+    pub(crate) topological_order: Vec<usize>,
+}
+
+impl Constraints {
+    /// `request` may only be included if `depends_on` is included
+    /// and executed before it.
+    pub fn add_dependency(&mut self, request: usize, depends_on: usize) {
+        self.dependencies.push((request, depends_on));
+    }
+
+    /// `a` and `b` cannot be both included.
+    pub fn add_conflict(&mut self, a: usize, b: usize) {
+        self.conflicts.push((a, b));
+    }
+
+    /// (request, depends_on) pairs.
+    pub fn dependencies(&self) -> &[(usize, usize)] {
+        &self.dependencies
+    }
+
+    /// pairs of mutually exclusive requests.
+    pub fn conflicts(&self) -> &[(usize, usize)] {
+        &self.conflicts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// Check that every request index is smaller than `num_requests`,
+    /// and that the dependency graph has no cycle.
+    pub fn validate(&self, num_requests: usize) -> Result<(), FeeMaximizerError> {
+        self.graph(num_requests).map(|_| ())
+    }
+
+    /// Check whether the included requests, executed in `request_order`,
+    /// satisfy all the constraints.
+    ///
+    /// `included[r]` tells whether request `r` is included,
+    /// and requests not in `request_order` are considered not included.
+    pub fn is_satisfied(&self, included: &[bool], request_order: &[usize]) -> bool {
+        let mut position = vec![usize::MAX; included.len()];
+        for (i, req) in request_order.iter().copied().enumerate() {
+            position[req] = i;
+        }
+        let is_included = |r: usize| included[r] && position[r] != usize::MAX;
+        self.dependencies
+            .iter()
+            .all(|(r, d)| !is_included(*r) || (is_included(*d) && position[*d] < position[*r]))
+            && self
+                .conflicts
+                .iter()
+                .all(|(a, b)| !(is_included(*a) && is_included(*b)))
+    }
+
+    /// Validate the constraints and build the adjacency lists.
+    pub(crate) fn graph(&self, num_requests: usize) -> Result<RequestGraph, FeeMaximizerError> {
+        let mut depends_on = vec![Vec::new(); num_requests];
+        let mut dependents = vec![Vec::new(); num_requests];
+        let mut conflicts = vec![Vec::new(); num_requests];
+        let check_index = |r: usize| {
+            if r < num_requests {
+                Ok(())
+            } else {
+                Err(FeeMaximizerError::RequestIndexOutOfRange {
+                    index: r,
+                    num_requests,
+                })
+            }
+        };
+        for (r, d) in self.dependencies.iter().copied() {
+            check_index(r)?;
+            check_index(d)?;
+            depends_on[r].push(d);
+            dependents[d].push(r);
+        }
+        for (a, b) in self.conflicts.iter().copied() {
+            check_index(a)?;
+            check_index(b)?;
+            if a == b {
+                return Err(FeeMaximizerError::SelfConflict(a));
+            }
+            conflicts[a].push(b);
+            conflicts[b].push(a);
+        }
+
+        // Kahn's algorithm.
+        let mut pending = depends_on.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut queue = (0..num_requests)
+            .filter(|r| pending[*r] == 0)
+            .collect::<VecDeque<_>>();
+        let mut topological_order = Vec::with_capacity(num_requests);
+        while let Some(r) = queue.pop_front() {
+            topological_order.push(r);
+            for dependent in dependents[r].iter().copied() {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        if topological_order.len() < num_requests {
+            let cycle = (0..num_requests).filter(|r| pending[*r] > 0).collect();
+            return Err(FeeMaximizerError::CyclicDependency(cycle));
+        }
+
+        Ok(RequestGraph {
+            depends_on,
+            dependents,
+            conflicts,
+            topological_order,
+        })
+    }
+}
+
+impl RequestGraph {
+    pub(crate) fn has_dependencies(&self) -> bool {
+        self.depends_on.iter().any(|d| !d.is_empty())
+    }
+}
+
+impl BlockCapacity {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_transactions.is_none() && self.max_weight.is_none()
+    }
+
+    /// Whether a block with the given usage fits in the capacity.
+    pub fn fits(&self, usage: BlockUsage) -> bool {
+        self.max_transactions
+            .is_none_or(|max| usage.transactions <= max)
+            && self.max_weight.is_none_or(|max| usage.weight <= max)
+    }
+}
+
+impl BlockUsage {
+    /// The usage of a list of transactions.
+    pub fn of<'a>(transactions: impl IntoIterator<Item = &'a Transaction>) -> Self {
+        transactions.into_iter().fold(Self::default(), |usage, tx| {
+            usage.with_transaction(tx.weight)
+        })
+    }
+
+    /// The usage after adding a transaction of the given weight.
+    pub fn with_transaction(self, weight: u64) -> Self {
+        Self {
+            transactions: self.transactions + 1,
+            weight: self.weight.saturating_add(weight),
+        }
+    }
+}
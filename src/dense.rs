@@ -0,0 +1,120 @@
+//! This file defines the dense balances used by the genetic solver.
+//!
+//! Every (address, asset) pair of a problem is interned into an integer ID
+//! once, so that evaluating a selection copies and updates a flat vector
+//! instead of cloning and hashing a `Balance`.
+use crate::{Address, Amount, Asset, Balance, Problem, SYSTEM_ADDRESS};
+use std::collections::HashMap;
+
+/// A transaction with its balances interned.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DenseTransaction {
+    /// balance of the sender in the asset of the amount.
+    from: usize,
+    /// balance of the sender in the asset of the fee.
+    from_fee: usize,
+    /// balance of the system in the asset of the fee.
+    system_fee: usize,
+    /// balance of the receiver in the asset of the amount.
+    to: usize,
+    amount: Amount,
+    fee: Amount,
+}
+
+/// A problem with its balances interned.
+pub(crate) struct DenseProblem {
+    /// (address, asset) of every ID.
+    keys: Vec<(Address, Asset)>,
+    /// initial balance of every ID, 0 if absent from the problem.
+    pub balance: Vec<Amount>,
+    /// transactions of every request.
+    pub requests: Vec<Vec<DenseTransaction>>,
+    /// ID of the system balance in the objective asset.
+    pub system: usize,
+}
+
+impl DenseProblem {
+    pub fn new(problem: &Problem) -> Self {
+        let mut ids = HashMap::new();
+        let mut keys = Vec::new();
+        let mut intern = |address: &Address, asset: &Asset| {
+            *ids.entry((address.clone(), asset.clone()))
+                .or_insert_with(|| {
+                    keys.push((address.clone(), asset.clone()));
+                    keys.len() - 1
+                })
+        };
+        let system = intern(&SYSTEM_ADDRESS, problem.objective);
+        let requests = problem
+            .requests
+            .iter()
+            .map(|request| {
+                request
+                    .transactions
+                    .iter()
+                    .map(|tx| DenseTransaction {
+                        from: intern(&tx.from, &tx.asset),
+                        from_fee: intern(&tx.from, &tx.fee_asset),
+                        system_fee: intern(&SYSTEM_ADDRESS, &tx.fee_asset),
+                        to: intern(&tx.to, &tx.asset),
+                        amount: tx.amount,
+                        fee: tx.fee,
+                    })
+                    .collect()
+            })
+            .collect();
+        let balance = keys
+            .iter()
+            .map(|key| problem.balance.get(key).copied().unwrap_or(Amount::ZERO))
+            .collect();
+        Self {
+            keys,
+            balance,
+            requests,
+            system,
+        }
+    }
+
+    /// Rebuild the balance after executing the `selected` (request, transaction) pairs,
+    /// given the dense `balance` after execution.
+    ///
+    /// Like [`apply_transaction`](crate::algo::apply_transaction),
+    /// every balance touched by an executed transaction is present.
+    pub fn to_balance(
+        &self,
+        initial: &Balance,
+        balance: &[Amount],
+        selected: &[(usize, usize)],
+    ) -> Balance {
+        let mut result = initial.clone();
+        for (req, tx) in selected.iter().copied() {
+            let tx = &self.requests[req][tx];
+            for id in [tx.from, tx.from_fee, tx.system_fee, tx.to] {
+                result.insert(self.keys[id].clone(), balance[id]);
+            }
+        }
+        result
+    }
+}
+
+/// Apply a single transaction to a dense balance,
+/// see [`apply_transaction`](crate::algo::apply_transaction).
+///
+/// Return false if the balance of the sender is not sufficient
+/// (or if a balance overflows), in which case the balance should be discarded.
+#[inline]
+pub(crate) fn apply_dense_transaction(balance: &mut [Amount], tx: &DenseTransaction) -> bool {
+    for (id, amount) in [(tx.from, tx.amount), (tx.from_fee, tx.fee)] {
+        match balance[id].checked_sub(amount) {
+            Some(remaining) if !remaining.is_negative() => balance[id] = remaining,
+            _ => return false,
+        }
+    }
+    for (id, amount) in [(tx.system_fee, tx.fee), (tx.to, tx.amount)] {
+        match balance[id].checked_add(amount) {
+            Some(new_balance) => balance[id] = new_balance,
+            None => return false,
+        }
+    }
+    true
+}
@@ -0,0 +1,208 @@
+//! This file defines the error type of the fee maximizer.
+use crate::{Address, Amount, Asset, BlockUsage};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Errors returned by the fee maximizer.
+#[derive(Debug)]
+pub enum FeeMaximizerError {
+    /// an address is empty.
+    EmptyAddress,
+    /// an asset is empty.
+    EmptyAsset,
+    /// a transaction sends to or from the system address.
+    SystemAddress,
+    /// a transaction has a negative amount or fee.
+    NegativeAmount,
+    /// a balance is negative.
+    NegativeBalance,
+    /// a balance or a total fee overflows.
+    AmountOverflow,
+    /// a string passed through the C interface is not valid UTF-8.
+    InvalidUtf8,
+    /// invalid solver parameters.
+    InvalidSolverParams(String),
+    /// no built-in solver has this name.
+    UnknownSolver(String),
+    /// a constraint refers to a request that does not exist.
+    RequestIndexOutOfRange { index: usize, num_requests: usize },
+    /// a request conflicts with itself.
+    SelfConflict(usize),
+    /// the dependencies between these requests form a cycle.
+    CyclicDependency(Vec<usize>),
+    /// a solver returned a solution exceeding the block capacity.
+    CapacityExceeded { solver: String, usage: BlockUsage },
+    /// a solver returned an invalid selection, e.g. one breaking a constraint.
+    InvalidSolution { solver: String, reason: String },
+    /// a csv record cannot be parsed.
+    ///
+    /// `line` and `column` start at 1 and are `None` when unknown.
+    Csv {
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+    /// a JSON document cannot be parsed.
+    Json(serde_json::Error),
+    /// an io error, e.g. a file cannot be opened.
+    Io(io::Error),
+    /// a custom solver failed.
+    Solver(String),
+    /// the sender of a verified transaction lacks `shortfall` of `asset`
+    /// to pay its amount and fee, `position` being its index in the solution.
+    InsufficientBalance {
+        position: usize,
+        address: Address,
+        asset: Asset,
+        shortfall: Amount,
+    },
+    /// a verified transaction is not a transaction of the submitted requests.
+    UnknownTransaction { position: usize, reason: String },
+    /// a verified solution breaks an atomic request, a constraint or the block capacity.
+    ConstraintViolated(String),
+    /// the total fee of a verified solution differs from the recomputed one.
+    FeeMismatch { claimed: Amount, actual: Amount },
+    /// a request added to a mempool has no id.
+    MissingRequestId,
+    /// a mempool already has a request with this id.
+    DuplicateRequestId(u64),
+    /// a mempool has no request with this id.
+    UnknownRequestId(u64),
+}
+
+impl Display for FeeMaximizerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyAddress => f.write_str("address cannot be empty"),
+            Self::EmptyAsset => f.write_str("asset cannot be empty"),
+            Self::SystemAddress => f.write_str("cannot send to or from system address"),
+            Self::NegativeAmount => f.write_str("amount and fee must be non-negative"),
+            Self::NegativeBalance => f.write_str("balance must be non-negative"),
+            Self::AmountOverflow => f.write_str("amount overflow"),
+            Self::InvalidUtf8 => f.write_str("string is not valid UTF-8"),
+            Self::InvalidSolverParams(reason) => write!(f, "invalid solver parameters: {reason}"),
+            Self::UnknownSolver(name) => write!(
+                f,
+                "unknown solver `{name}`, expected one of: {}",
+                crate::SOLVER_NAMES.join(", ")
+            ),
+            Self::RequestIndexOutOfRange {
+                index,
+                num_requests,
+            } => write!(
+                f,
+                "request index {index} out of range (there are {num_requests} requests)"
+            ),
+            Self::SelfConflict(request) => {
+                write!(f, "request {request} cannot conflict with itself")
+            }
+            Self::CyclicDependency(requests) => {
+                let requests = requests.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "cyclic dependency: requests {} cannot be ordered",
+                    requests.join(", ")
+                )
+            }
+            Self::CapacityExceeded { solver, usage } => write!(
+                f,
+                "solver `{solver}` exceeded the block capacity: {} transactions, weight {}",
+                usage.transactions, usage.weight
+            ),
+            Self::InvalidSolution { solver, reason } => {
+                write!(
+                    f,
+                    "solver `{solver}` returned an invalid solution: {reason}"
+                )
+            }
+            Self::Csv {
+                line,
+                column,
+                message,
+            } => {
+                f.write_str("csv error")?;
+                if let Some(line) = line {
+                    write!(f, " at line {line}")?;
+                }
+                if let Some(column) = column {
+                    write!(f, ", column {column}")?;
+                }
+                write!(f, ": {message}")
+            }
+            Self::Json(e) => write!(f, "json error: {e}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Solver(reason) => f.write_str(reason),
+            Self::InsufficientBalance {
+                position,
+                address,
+                asset,
+                shortfall,
+            } => write!(
+                f,
+                "transaction {position} cannot be paid: {address} lacks {shortfall} {asset}"
+            ),
+            Self::UnknownTransaction { position, reason } => {
+                write!(f, "transaction {position} was not submitted: {reason}")
+            }
+            Self::ConstraintViolated(reason) => write!(f, "constraint violated: {reason}"),
+            Self::FeeMismatch { claimed, actual } => write!(
+                f,
+                "claimed total fee {claimed} differs from the actual total fee {actual}"
+            ),
+            Self::MissingRequestId => f.write_str("a request in a mempool must have an id"),
+            Self::DuplicateRequestId(id) => write!(f, "request id {id} is already in the mempool"),
+            Self::UnknownRequestId(id) => write!(f, "request id {id} is not in the mempool"),
+        }
+    }
+}
+
+impl Error for FeeMaximizerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Json(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FeeMaximizerError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<csv::Error> for FeeMaximizerError {
+    /// Keep the position of the error, unwrapping io errors.
+    fn from(e: csv::Error) -> Self {
+        let line = e.position().map(|p| p.line());
+        if let csv::ErrorKind::Deserialize { err, .. } = e.kind() {
+            return Self::Csv {
+                line,
+                column: err.field().map(|c| c + 1),
+                message: err.kind().to_string(),
+            };
+        }
+        let message = e.to_string();
+        match e.into_kind() {
+            csv::ErrorKind::Io(e) => Self::Io(e),
+            _ => Self::Csv {
+                line,
+                column: None,
+                message,
+            },
+        }
+    }
+}
+
+impl From<serde_json::Error> for FeeMaximizerError {
+    /// Unwrap io errors, keep the others with their position.
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            Self::Io(e.into())
+        } else {
+            Self::Json(e)
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! This file defines an exact branch-and-bound solver for the fee maximizer.
+//!
+//! Unlike the genetic search in `algo.rs`, the search here is exhaustive,
+//! so the returned fee is provably optimal. The running time is exponential
+//! in the number of transactions, so it is only meant for small batches.
+use crate::algo::apply_transaction;
+use crate::constraints::RequestGraph;
+use crate::{Amount, Asset, Balance, BlockCapacity, BlockUsage, Problem, Request};
+
+pub(crate) fn maximize_fee_exact(
+    problem: &Problem,
+    graph: &RequestGraph,
+) -> (Vec<(usize, usize)>, Balance, usize) {
+    let balance = problem.balance;
+    let requests = problem.requests;
+    let objective = problem.objective;
+    let request_fees = requests
+        .iter()
+        .map(|r| {
+            r.transactions.iter().fold(Amount::ZERO, |sum, tx| {
+                sum.saturating_add(tx.fee_in(objective))
+            })
+        })
+        .collect::<Vec<_>>();
+    // explore requests with larger total fee first to find good bounds early.
+    let mut request_order = (0..requests.len()).collect::<Vec<_>>();
+    request_order.sort_by_key(|r| std::cmp::Reverse(request_fees[*r]));
+
+    let mut search = BranchAndBound {
+        requests,
+        graph,
+        objective,
+        capacity: problem.capacity,
+        usage: BlockUsage::default(),
+        remaining_fee: request_fees
+            .iter()
+            .fold(Amount::ZERO, |sum, fee| sum.saturating_add(*fee)),
+        request_fees,
+        request_order,
+        used: vec![false; requests.len()],
+        path: Vec::new(),
+        best_fee: Amount::from_minor(-1),
+        best_path: Vec::new(),
+        best_balance: balance.clone(),
+        nodes: 0,
+    };
+    search.next_request(balance, Amount::ZERO);
+
+    (search.best_path, search.best_balance, search.nodes)
+}
+
+/// Depth first search over the execution sequence.
+///
+/// The sequence is built request by request. Once a request is opened,
+/// its transactions are decided (include or skip) in their original order,
+/// and only feasible transactions are included.
+/// Transactions of an opened atomic request can never be skipped.
+/// A request can only be opened after all its dependencies,
+/// and never after a conflicting request.
+/// A transaction can only be included if it fits in the block capacity.
+struct BranchAndBound<'a> {
+    requests: &'a [Request],
+    graph: &'a RequestGraph,
+    /// only fees in this asset are collected.
+    objective: &'a Asset,
+    capacity: BlockCapacity,
+    /// capacity consumed by the transactions in `path`.
+    usage: BlockUsage,
+    request_fees: Vec<Amount>,
+    request_order: Vec<usize>,
+    used: Vec<bool>,
+    /// total fee of all requests not yet opened.
+    remaining_fee: Amount,
+    /// (request index, transaction index) in execution order.
+    path: Vec<(usize, usize)>,
+    best_fee: Amount,
+    best_path: Vec<(usize, usize)>,
+    best_balance: Balance,
+    /// number of search nodes visited.
+    nodes: usize,
+}
+
+impl<'a> BranchAndBound<'a> {
+    /// Called between two requests: every such state is a feasible solution.
+    fn next_request(&mut self, balance: &Balance, fee: Amount) {
+        self.nodes += 1;
+        if fee > self.best_fee {
+            self.best_fee = fee;
+            self.best_path = self.path.clone();
+            self.best_balance = balance.clone();
+        }
+        // upper bound: even collecting every remaining fee cannot do better.
+        if fee.saturating_add(self.remaining_fee) <= self.best_fee {
+            return;
+        }
+        // the block is full.
+        if self.capacity.max_transactions == Some(self.usage.transactions) {
+            return;
+        }
+        for i in 0..self.request_order.len() {
+            let req = self.request_order[i];
+            if self.used[req] || !self.can_open(req) {
+                continue;
+            }
+            self.used[req] = true;
+            self.remaining_fee -= self.request_fees[req];
+            self.within_request(req, 0, balance, fee, false, self.request_fees[req]);
+            self.remaining_fee += self.request_fees[req];
+            self.used[req] = false;
+        }
+    }
+
+    /// Whether all dependencies of a request are included, and none of its conflicts.
+    fn can_open(&self, req: usize) -> bool {
+        self.graph.depends_on[req].iter().all(|d| self.used[*d])
+            && self.graph.conflicts[req].iter().all(|c| !self.used[*c])
+    }
+
+    /// Decide whether to include the `tx`-th transaction of request `req`.
+    fn within_request(
+        &mut self,
+        req: usize,
+        tx: usize,
+        balance: &Balance,
+        fee: Amount,
+        any_selected: bool,
+        request_remaining_fee: Amount,
+    ) {
+        self.nodes += 1;
+        let requests = self.requests;
+        let request = &requests[req];
+        if tx == request.transactions.len() {
+            // a request with nothing selected is the same as not opening it.
+            if any_selected {
+                self.next_request(balance, fee);
+            }
+            return;
+        }
+        if fee
+            .saturating_add(request_remaining_fee)
+            .saturating_add(self.remaining_fee)
+            <= self.best_fee
+        {
+            return;
+        }
+        let transaction = &request.transactions[tx];
+        let tx_fee = transaction.fee_in(self.objective);
+        let rest_fee = request_remaining_fee - tx_fee;
+
+        // include the transaction if it is feasible and fits in the block.
+        let usage = self.usage;
+        let new_usage = usage.with_transaction(transaction.weight);
+        let mut new_balance = balance.clone();
+        if self.capacity.fits(new_usage) && apply_transaction(&mut new_balance, transaction) {
+            self.path.push((req, tx));
+            self.usage = new_usage;
+            self.within_request(req, tx + 1, &new_balance, fee + tx_fee, true, rest_fee);
+            self.usage = usage;
+            self.path.pop();
+        }
+
+        // skip the transaction.
+        if !request.atomic {
+            self.within_request(req, tx + 1, balance, fee, any_selected, rest_fee);
+        }
+    }
+}
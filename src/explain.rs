@@ -0,0 +1,282 @@
+//! This file defines the explanation of why transactions are not selected,
+//! e.g. to answer a user whose transaction was dropped.
+use crate::algo::apply_transaction;
+use crate::verify::shortfall;
+use crate::{
+    Amount, Asset, Balance, BlockUsage, ExecutedTransaction, FeeMaximizer, FeeMaximizerError,
+    Solution, Transaction, SYSTEM_ADDRESS,
+};
+use std::fmt::{Display, Formatter};
+
+/// Why a transaction is not executed.
+///
+/// Positions refer to the executed transactions of the solution, in execution order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RejectionReason {
+    /// its request conflicts with this executed request.
+    Conflict(usize),
+    /// its request depends on this request, which is not executed.
+    MissingDependency(usize),
+    /// the sender lacks `shortfall` of `asset` at its position, even if the transactions
+    /// executed before it did not spend the same funds.
+    Infeasible { asset: Asset, shortfall: Amount },
+    /// the funds of the sender in `asset` are spent by the executed transactions at these positions.
+    Blocked { asset: Asset, by: Vec<usize> },
+    /// the sender cannot pay both, and the executed transaction at this position pays
+    /// at least the same fee in the objective asset.
+    LowerFee {
+        alternative: usize,
+        fee: Amount,
+        alternative_fee: Amount,
+    },
+    /// it could be paid, but this other transaction of its atomic request could not.
+    Atomic { index: usize },
+    /// the block has no room left for it.
+    BlockFull,
+    /// it could have been appended to the block: the solver did not find it.
+    Missed,
+}
+
+/// The reason why a transaction of the solution is not executed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    /// index of the request (i.e., the order in which it is added to the optimizer).
+    pub request: usize,
+    /// user-supplied id of the request, if any.
+    pub request_id: Option<u64>,
+    /// position of the transaction within the request.
+    pub index: usize,
+    pub reason: RejectionReason,
+}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict(request) => write!(f, "conflicts with executed request {request}"),
+            Self::MissingDependency(request) => {
+                write!(f, "depends on request {request}, which is not executed")
+            }
+            Self::Infeasible { asset, shortfall } => {
+                write!(f, "infeasible: the sender lacks {shortfall} {asset}")
+            }
+            Self::Blocked { asset, by } => {
+                let by = by.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "blocked: the {asset} of the sender is spent by the transactions at positions {}",
+                    by.join(", ")
+                )
+            }
+            Self::LowerFee {
+                alternative,
+                fee,
+                alternative_fee,
+            } => write!(
+                f,
+                "lower fee: its fee {fee} is not above the fee {alternative_fee} \
+                 of the competing transaction at position {alternative}"
+            ),
+            Self::Atomic { index } => write!(
+                f,
+                "transaction {index} of its atomic request cannot be executed"
+            ),
+            Self::BlockFull => f.write_str("the block is full"),
+            Self::Missed => f.write_str("it could be executed, but was not found by the solver"),
+        }
+    }
+}
+
+impl FeeMaximizer {
+    /// Explain why every rejected transaction of a solution is not executed,
+    /// in the order of `solution.rejected`.
+    ///
+    /// Call it right after solving: the balance before the block is recovered from the
+    /// current balance, unless the solution was not committed because of audit violations.
+    /// A rejected transaction is judged at its position in the block, i.e. right before the
+    /// next executed transaction of its request, or at the end of the block.
+    pub fn explain(&self, solution: &Solution) -> Result<Vec<Explanation>, FeeMaximizerError> {
+        let graph = self.constraints.graph(self.requests.len())?;
+        // (index, position) of the executed transactions of every request.
+        let mut executed = vec![Vec::new(); self.requests.len()];
+        for (position, e) in solution.executed.iter().enumerate() {
+            executed
+                .get_mut(e.request)
+                .ok_or(FeeMaximizerError::RequestIndexOutOfRange {
+                    index: e.request,
+                    num_requests: self.requests.len(),
+                })?
+                .push((e.index, position));
+        }
+        let included = executed.iter().map(|e| !e.is_empty()).collect::<Vec<_>>();
+        let usage = BlockUsage::of(solution.transactions());
+
+        let mut rejected = Vec::with_capacity(solution.rejected.len());
+        for r in &solution.rejected {
+            let request =
+                self.requests
+                    .get(r.request)
+                    .ok_or(FeeMaximizerError::RequestIndexOutOfRange {
+                        index: r.request,
+                        num_requests: self.requests.len(),
+                    })?;
+            let tx = request.transactions.get(r.index).ok_or(
+                FeeMaximizerError::RequestIndexOutOfRange {
+                    index: r.index,
+                    num_requests: request.transactions.len(),
+                },
+            )?;
+            let position = match executed[r.request].iter().find(|(i, _)| *i > r.index) {
+                Some((_, position)) => *position,
+                None => executed[r.request]
+                    .last()
+                    .map_or(solution.executed.len(), |(_, position)| position + 1),
+            };
+            rejected.push((r, request, tx, position));
+        }
+
+        // replay the block, explaining the rejected transactions by position.
+        let mut order = (0..rejected.len()).collect::<Vec<_>>();
+        order.sort_by_key(|k| rejected[*k].3);
+        let mut balance = if solution.violations.is_empty() {
+            revert(&self.balance, &solution.executed)?
+        } else {
+            self.balance.clone()
+        };
+        let mut replayed = 0;
+        let mut reasons = vec![None; rejected.len()];
+        for k in order {
+            let (r, request, tx, position) = rejected[k];
+            for e in &solution.executed[replayed..position] {
+                apply_transaction(&mut balance, &e.transaction);
+            }
+            replayed = position;
+            let conflict = graph.conflicts[r.request].iter().find(|r| included[**r]);
+            let missing = graph.depends_on[r.request].iter().find(|r| !included[**r]);
+            reasons[k] = Some(if let Some(conflict) = conflict {
+                RejectionReason::Conflict(*conflict)
+            } else if let Some(missing) = missing {
+                RejectionReason::MissingDependency(*missing)
+            } else if let Some((asset, missing)) = shortfall(&balance, tx) {
+                self.explain_shortfall(&solution.executed[..position], tx, asset, missing)
+            } else if let Some(index) = request
+                .atomic
+                .then(|| unpayable(&balance, &request.transactions))
+                .flatten()
+            {
+                RejectionReason::Atomic { index }
+            } else if !self.capacity.fits(usage.with_transaction(tx.weight)) {
+                RejectionReason::BlockFull
+            } else {
+                RejectionReason::Missed
+            });
+        }
+        Ok(rejected
+            .into_iter()
+            .zip(reasons)
+            .map(|((r, ..), reason)| Explanation {
+                request: r.request,
+                request_id: r.request_id,
+                index: r.index,
+                // every rejected transaction is explained above.
+                reason: reason.unwrap(),
+            })
+            .collect())
+    }
+
+    /// Tell whether the sender cannot pay `tx` at all, or only because of the
+    /// transactions executed before it spending the same funds.
+    fn explain_shortfall(
+        &self,
+        before: &[ExecutedTransaction],
+        tx: &Transaction,
+        asset: &Asset,
+        missing: Amount,
+    ) -> RejectionReason {
+        // what the executed transactions of the sender spend in the lacking asset.
+        let competitors = before
+            .iter()
+            .map(|e| &e.transaction)
+            .enumerate()
+            .filter(|(_, c)| c.from == tx.from)
+            .map(|(position, c)| (position, c, spending(c, asset)))
+            .filter(|(_, _, spent)| *spent > 0)
+            .collect::<Vec<_>>();
+        let spent = competitors.iter().map(|(_, _, s)| s).sum::<i128>();
+        let missing = missing.minor() as i128;
+        if missing > spent {
+            return RejectionReason::Infeasible {
+                asset: asset.clone(),
+                shortfall: Amount::from_minor((missing - spent).min(i64::MAX as i128) as i64),
+            };
+        }
+        // a single competitor paying at least the same fee, whose funds would be enough.
+        let fee = tx.fee_in(&self.objective);
+        let alternative = competitors
+            .iter()
+            .filter(|(_, c, spent)| *spent >= missing && c.fee_in(&self.objective) >= fee)
+            .min_by_key(|(_, c, _)| c.fee_in(&self.objective));
+        match alternative {
+            Some((position, c, _)) => RejectionReason::LowerFee {
+                alternative: *position,
+                fee,
+                alternative_fee: c.fee_in(&self.objective),
+            },
+            None => RejectionReason::Blocked {
+                asset: asset.clone(),
+                by: competitors
+                    .iter()
+                    .map(|(position, _, _)| *position)
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// What the sender of a transaction spends in an asset, in minor units.
+fn spending(tx: &Transaction, asset: &Asset) -> i128 {
+    let mut spent = 0;
+    if tx.asset == *asset {
+        spent += tx.amount.minor() as i128;
+    }
+    if tx.fee_asset == *asset {
+        spent += tx.fee.minor() as i128;
+    }
+    spent
+}
+
+/// The index of the first transaction that cannot be paid
+/// when appending all the transactions to the balance, if any.
+fn unpayable(balance: &Balance, transactions: &[Transaction]) -> Option<usize> {
+    let mut balance = balance.clone();
+    transactions
+        .iter()
+        .position(|tx| !apply_transaction(&mut balance, tx))
+}
+
+/// The balance before executing the transactions, from the balance after.
+fn revert(
+    balance: &Balance,
+    executed: &[ExecutedTransaction],
+) -> Result<Balance, FeeMaximizerError> {
+    let mut balance = balance.clone();
+    for tx in executed.iter().rev().map(|e| &e.transaction) {
+        // credit the sender back, and debit the recipients.
+        for (address, asset, amount, credit) in [
+            (&tx.to, &tx.asset, tx.amount, false),
+            (&*SYSTEM_ADDRESS, &tx.fee_asset, tx.fee, false),
+            (&tx.from, &tx.fee_asset, tx.fee, true),
+            (&tx.from, &tx.asset, tx.amount, true),
+        ] {
+            let entry = balance
+                .entry((address.clone(), asset.clone()))
+                .or_insert(Amount::ZERO);
+            let reverted = if credit {
+                entry.checked_add(amount)
+            } else {
+                entry.checked_sub(amount)
+            };
+            *entry = reverted.ok_or(FeeMaximizerError::AmountOverflow)?;
+        }
+    }
+    Ok(balance)
+}
@@ -0,0 +1,91 @@
+//! This file defines a greedy heuristic for the fee maximizer.
+//!
+//! At each step, the request that brings the largest fee (given the current
+//! balance) is executed next, until no remaining request can bring any fee.
+//! Only requests whose dependencies are executed and which do not conflict
+//! with any executed request are considered, and transactions that do not
+//! fit in the remaining block capacity are skipped.
+//! It is fast but offers no optimality guarantee.
+use crate::algo::apply_transaction;
+use crate::constraints::RequestGraph;
+use crate::{Amount, Balance, BlockUsage, Problem, Request};
+
+/// (fee collected, balance after execution, usage after execution, executed transactions)
+type Execution = (Amount, Balance, BlockUsage, Vec<usize>);
+
+pub(crate) fn maximize_fee_greedy(
+    problem: &Problem,
+    graph: &RequestGraph,
+) -> (Vec<(usize, usize)>, Balance, usize) {
+    let requests = problem.requests;
+    let mut balance = problem.balance.clone();
+    let mut usage = BlockUsage::default();
+    let mut used = vec![false; requests.len()];
+    let mut txs = Vec::new();
+    let mut evaluations = 0usize;
+    loop {
+        // (fee, request index) of the most profitable request.
+        let mut best: Option<(Amount, usize)> = None;
+        for (req, request) in requests.iter().enumerate() {
+            if used[req]
+                || graph.depends_on[req].iter().any(|d| !used[*d])
+                || graph.conflicts[req].iter().any(|c| used[*c])
+            {
+                continue;
+            }
+            evaluations += 1;
+            let (fee, _, _, _) = execute_feasible(&balance, usage, problem, request);
+            if fee > best.map(|(f, _)| f).unwrap_or(Amount::ZERO) {
+                best = Some((fee, req));
+            }
+        }
+        match best {
+            Some((_, req)) => {
+                let (_, new_balance, new_usage, selected) =
+                    execute_feasible(&balance, usage, problem, &requests[req]);
+                used[req] = true;
+                balance = new_balance;
+                usage = new_usage;
+                txs.extend(selected.into_iter().map(|tx| (req, tx)));
+            }
+            None => break,
+        }
+    }
+    (txs, balance, evaluations)
+}
+
+/// Execute every feasible transaction of a request in order, skipping the ones
+/// with insufficient balance or not fitting in the remaining capacity.
+/// Nothing is executed if an atomic request cannot be executed fully.
+///
+/// Return the fee collected in the objective asset, the new balance and usage,
+/// and the executed transactions.
+fn execute_feasible(
+    initial_balance: &Balance,
+    initial_usage: BlockUsage,
+    problem: &Problem,
+    request: &Request,
+) -> Execution {
+    let mut balance = initial_balance.clone();
+    let mut usage = initial_usage;
+    let mut fee = Amount::ZERO;
+    let mut selected = Vec::new();
+    for (i, tx) in request.transactions.iter().enumerate() {
+        let new_usage = usage.with_transaction(tx.weight);
+        let mut new_balance = balance.clone();
+        if problem.capacity.fits(new_usage) && apply_transaction(&mut new_balance, tx) {
+            balance = new_balance;
+            usage = new_usage;
+            fee += tx.fee_in(problem.objective);
+            selected.push(i);
+        } else if request.atomic {
+            return (
+                Amount::ZERO,
+                initial_balance.clone(),
+                initial_usage,
+                Vec::new(),
+            );
+        }
+    }
+    (fee, balance, usage, selected)
+}
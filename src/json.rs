@@ -0,0 +1,183 @@
+//! This file defines the JSON documents read and written by the fee maximizer:
+//! a balances map keyed by address, a requests array and the solution.
+use crate::{
+    Address, Amount, Asset, FeeMaximizer, FeeMaximizerError, Request, Solution, Transaction,
+    NATIVE_ASSET,
+};
+use serde::de::{self, IntoDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Formatter;
+use std::io::{Read, Write};
+
+/// The balance of an address in a balances map: either an amount
+/// in the native asset, or a map from asset to amount.
+enum AddressBalance {
+    Native(Amount),
+    Assets(BTreeMap<String, Amount>),
+}
+
+impl<'de> Deserialize<'de> for AddressBalance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AddressBalanceVisitor;
+
+        impl<'de> Visitor<'de> for AddressBalanceVisitor {
+            type Value = AddressBalance;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                f.write_str("an amount or a map from asset to amount")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Amount::deserialize(v.into_deserializer()).map(AddressBalance::Native)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Amount::deserialize(v.into_deserializer()).map(AddressBalance::Native)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Amount::deserialize(v.into_deserializer()).map(AddressBalance::Native)
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Amount::deserialize(v.into_deserializer()).map(AddressBalance::Native)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                BTreeMap::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(AddressBalance::Assets)
+            }
+        }
+
+        deserializer.deserialize_any(AddressBalanceVisitor)
+    }
+}
+
+/// A request as written in JSON, validated when converted into a [`Request`].
+#[derive(Deserialize)]
+pub(crate) struct RequestDocument {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    atomic: bool,
+    transactions: Vec<Transaction>,
+}
+
+impl TryFrom<RequestDocument> for Request {
+    type Error = FeeMaximizerError;
+
+    fn try_from(document: RequestDocument) -> Result<Self, Self::Error> {
+        let mut request = Request::init_empty();
+        request.atomic = document.atomic;
+        request.id = document.id;
+        for tx in document.transactions {
+            request.add_transaction(tx)?;
+        }
+        Ok(request)
+    }
+}
+
+impl FeeMaximizer {
+    /// initialize from a JSON balances map and a JSON requests array,
+    /// see [`add_balance_from_json`](Self::add_balance_from_json)
+    /// and [`add_requests_from_json`](Self::add_requests_from_json).
+    pub fn init_from_json<B: Read, R: Read>(
+        balances: B,
+        requests: R,
+    ) -> Result<Self, FeeMaximizerError> {
+        let mut fee_maximizer = Self::init_empty();
+        fee_maximizer.add_balance_from_json(balances)?;
+        fee_maximizer.add_requests_from_json(requests)?;
+        Ok(fee_maximizer)
+    }
+
+    /// add balance from a JSON object mapping every address to either
+    /// an amount in the native asset or an object mapping assets to amounts,
+    /// e.g. `{"A": "100.5", "B": {"Native": 3, "USD": "12"}}`.
+    ///
+    /// Amounts are decimal strings or numbers. Numbers with more than
+    /// 15 significant digits are rejected since they may not be exact.
+    /// Nothing is added if any balance is invalid.
+    pub fn add_balance_from_json<R: Read>(&mut self, reader: R) -> Result<(), FeeMaximizerError> {
+        let document: BTreeMap<String, AddressBalance> = serde_json::from_reader(reader)?;
+        let mut added = HashMap::new();
+        for (address, balance) in document {
+            let address = Address::from_string(address)?;
+            let assets = match balance {
+                AddressBalance::Native(amount) => vec![(NATIVE_ASSET.clone(), amount)],
+                AddressBalance::Assets(assets) => assets
+                    .into_iter()
+                    .map(|(asset, amount)| Ok((Asset::from_string(asset)?, amount)))
+                    .collect::<Result<_, FeeMaximizerError>>()?,
+            };
+            for (asset, amount) in assets {
+                if amount.is_negative() {
+                    return Err(FeeMaximizerError::NegativeBalance);
+                }
+                let key = (address.clone(), asset);
+                let current = self.balance.get(&key).copied().unwrap_or(Amount::ZERO);
+                let entry = added.entry(key).or_insert(current);
+                *entry = entry
+                    .checked_add(amount)
+                    .ok_or(FeeMaximizerError::AmountOverflow)?;
+            }
+        }
+        self.balance.extend(added);
+        Ok(())
+    }
+
+    /// add requests from a JSON array of objects with the fields `transactions`,
+    /// and the optional fields `id` and `atomic`, e.g.
+    /// `[{"id": 1, "atomic": true, "transactions": [{"from": "A", "to": "B", "amount": "1", "fee": 0.1}]}]`.
+    ///
+    /// Transactions have the fields `from`, `to`, `amount` and `fee`,
+    /// and the optional fields `asset`, `fee_asset` and `weight`.
+    /// Requests are added in order. Nothing is added if any request is invalid.
+    pub fn add_requests_from_json<R: Read>(&mut self, reader: R) -> Result<(), FeeMaximizerError> {
+        let documents: Vec<RequestDocument> = serde_json::from_reader(reader)?;
+        let requests = documents
+            .into_iter()
+            .map(Request::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.requests.extend(requests);
+        Ok(())
+    }
+
+    /// write the balance as a JSON object mapping every address to an object
+    /// mapping assets to amounts, which can be read back by `add_balance_from_json`.
+    ///
+    /// Amounts are written as decimal strings to keep full precision.
+    pub fn write_balance_json<W: Write>(&self, writer: W) -> Result<(), FeeMaximizerError> {
+        let mut document: BTreeMap<String, BTreeMap<String, Amount>> = BTreeMap::new();
+        for ((address, asset), amount) in &self.balance {
+            document
+                .entry(address.to_string())
+                .or_default()
+                .insert(asset.to_string(), *amount);
+        }
+        serde_json::to_writer_pretty(writer, &document)?;
+        Ok(())
+    }
+
+    /// write the requests as a JSON array, which can be read back by `add_requests_from_json`.
+    pub fn write_requests_json<W: Write>(&self, writer: W) -> Result<(), FeeMaximizerError> {
+        serde_json::to_writer_pretty(writer, &self.requests)?;
+        Ok(())
+    }
+}
+
+impl Solution {
+    /// read a solution written by [`write_json`](Self::write_json), e.g. to verify it.
+    pub fn read_json<R: Read>(reader: R) -> Result<Self, FeeMaximizerError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// write the solution as a JSON object.
+    ///
+    /// Amounts are written as decimal strings to keep full precision.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), FeeMaximizerError> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
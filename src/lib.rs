@@ -1,76 +1,282 @@
 mod algo;
+mod amount;
+mod audit;
 mod c;
+mod constraints;
+mod dense;
+mod error;
+mod exact;
+mod explain;
+mod greedy;
+mod json;
+mod mempool;
+mod solution;
+mod solver;
+mod synthetic;
+mod verify;
 
 #[macro_use]
 extern crate lazy_static;
-use crate::algo::maximize_fee;
 use csv::Trim;
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use std::io;
+use std::fs::File;
+use std::io::{Read, Write};
 use std::ops::Deref;
 use std::path::Path;
 
+pub use crate::amount::Amount;
+pub use crate::audit::Violation;
+pub use crate::constraints::{BlockCapacity, BlockUsage, Constraints};
+pub use crate::error::FeeMaximizerError;
+pub use crate::explain::{Explanation, RejectionReason};
+pub use crate::mempool::Mempool;
+pub use crate::solution::{ExecutedTransaction, RejectedTransaction, Solution};
+pub use crate::solver::{
+    solver_from_name, CancellationToken, ExactSolver, GeneticConfig, GeneticSolver, GreedySolver,
+    Problem, Progress, ProgressCallback, SelectionCurve, Solver, SolverConfig, SolverOutput,
+    SolverStats, StopReason, WarmStart, SOLVER_NAMES,
+};
+pub use crate::synthetic::{BalanceDistribution, MempoolGenerator};
+pub use crate::verify::{verify, Verification};
+
 pub const SYSTEM_ADDRESS_STR: &str = "System";
 
+/// the asset of balances and transactions when none is specified.
+pub const NATIVE_ASSET_STR: &str = "Native";
+
 lazy_static! {
     pub static ref SYSTEM_ADDRESS: Address = Address(SYSTEM_ADDRESS_STR.as_bytes().to_vec());
+    pub static ref NATIVE_ASSET: Asset = Asset(NATIVE_ASSET_STR.as_bytes().to_vec());
 }
 
+/// balance of every address in every asset
+pub type Balance = HashMap<(Address, Asset), Amount>;
+
 /// defining address
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub struct Address(Vec<u8>);
 
+/// defining asset (e.g., a token symbol)
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct Asset(Vec<u8>);
+
 /// defining transaction
-#[derive(Clone, Debug)]
+///
+/// In JSON, `asset` and `fee_asset` default to the native asset and `weight` to 0.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     pub from: Address,
     pub to: Address,
-    pub amount: f64,
-    pub fee: f64,
+    pub amount: Amount,
+    pub fee: Amount,
+    /// asset of `amount`.
+    #[serde(default = "native_asset")]
+    pub asset: Asset,
+    /// asset of `fee`.
+    #[serde(default = "native_asset")]
+    pub fee_asset: Asset,
+    /// resource consumed in a block (e.g., gas or byte size).
+    #[serde(default)]
+    pub weight: u64,
 }
 
 /// defining request
-#[derive(Clone)]
-pub struct Request(Vec<Transaction>);
+///
+/// Deserializing a request validates its transactions like `add_transaction`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "json::RequestDocument")]
+pub struct Request {
+    /// user-supplied id reported in the solution.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    /// an atomic request is either executed fully or not at all.
+    atomic: bool,
+    transactions: Vec<Transaction>,
+}
+
+/// a row of a balance csv
+#[derive(Serialize, Deserialize)]
+struct BalanceEntry {
+    #[serde(rename = "User")]
+    address: String,
+    #[serde(rename = "Asset", default)]
+    asset: Option<String>,
+    #[serde(rename = "Balance", deserialize_with = "Amount::deserialize_str")]
+    amount: Amount,
+}
+
+/// a row of a balance csv without header, read by position:
+/// User, Balance, and an optional trailing Asset.
+#[derive(Deserialize)]
+struct HeaderlessBalanceEntry(
+    String,
+    #[serde(deserialize_with = "Amount::deserialize_str")] Amount,
+    #[serde(default)] Option<String>,
+);
+
+/// a row of a requests csv
+#[derive(Serialize, Deserialize)]
+struct RequestEntry {
+    request: u64,
+    from: String,
+    to: String,
+    #[serde(deserialize_with = "Amount::deserialize_str")]
+    amount: Amount,
+    #[serde(deserialize_with = "Amount::deserialize_str")]
+    fee: Amount,
+    #[serde(default)]
+    atomic: bool,
+    #[serde(default)]
+    weight: u64,
+    #[serde(default)]
+    asset: Option<String>,
+    #[serde(default)]
+    fee_asset: Option<String>,
+}
 
 /// the optimizer
+#[derive(Clone)]
 pub struct FeeMaximizer {
-    balance: HashMap<Address, f64>,
+    balance: Balance,
     requests: Vec<Request>,
+    constraints: Constraints,
+    capacity: BlockCapacity,
+    /// only fees paid in this asset are maximized.
+    objective: Asset,
+    /// check the invariants of every solution, see [`set_audit`](Self::set_audit).
+    audit: bool,
+    stats: Option<SolverStats>,
 }
 
 impl Address {
-    pub fn from_string(s: String) -> Result<Self, String> {
+    pub fn from_string(s: String) -> Result<Self, FeeMaximizerError> {
         if s.is_empty() {
-            return Err("address cannot be empty".to_string());
+            return Err(FeeMaximizerError::EmptyAddress);
         }
         Ok(Self(s.into_bytes()))
     }
 }
 
+impl Asset {
+    pub fn from_string(s: String) -> Result<Self, FeeMaximizerError> {
+        if s.is_empty() {
+            return Err(FeeMaximizerError::EmptyAsset);
+        }
+        Ok(Self(s.into_bytes()))
+    }
+}
+
+fn native_asset() -> Asset {
+    NATIVE_ASSET.clone()
+}
+
+impl Transaction {
+    /// Construct a transaction in the native asset with zero weight.
+    pub fn new(from: Address, to: Address, amount: Amount, fee: Amount) -> Self {
+        Self {
+            from,
+            to,
+            amount,
+            fee,
+            asset: NATIVE_ASSET.clone(),
+            fee_asset: NATIVE_ASSET.clone(),
+            weight: 0,
+        }
+    }
+
+    /// Set the asset of the amount.
+    pub fn with_asset(mut self, asset: Asset) -> Self {
+        self.asset = asset;
+        self
+    }
+
+    /// Set the asset of the fee.
+    pub fn with_fee_asset(mut self, fee_asset: Asset) -> Self {
+        self.fee_asset = fee_asset;
+        self
+    }
+
+    /// Set the weight (e.g., gas or byte size) of the transaction.
+    pub fn with_weight(mut self, weight: u64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// The fee if it is paid in `asset`, zero otherwise.
+    pub fn fee_in(&self, asset: &Asset) -> Amount {
+        if &self.fee_asset == asset {
+            self.fee
+        } else {
+            Amount::ZERO
+        }
+    }
+}
+
 impl Request {
     /// initialize an empty request.
     pub fn init_empty() -> Self {
-        Self(Vec::new())
+        Self {
+            transactions: Vec::new(),
+            atomic: false,
+            id: None,
+        }
+    }
+
+    /// initialize an empty atomic request,
+    /// whose transactions are either all executed or none executed.
+    pub fn init_atomic() -> Self {
+        Self {
+            transactions: Vec::new(),
+            atomic: true,
+            id: None,
+        }
     }
 
     /// if transaction is valid, add it to the request.
-    pub fn add_transaction(&mut self, tx: Transaction) -> Result<(), String> {
+    pub fn add_transaction(&mut self, tx: Transaction) -> Result<(), FeeMaximizerError> {
         if tx.from.0.is_empty() || tx.to.0.is_empty() {
-            return Err("address cannot be empty".to_string());
+            return Err(FeeMaximizerError::EmptyAddress);
+        }
+        if tx.asset.0.is_empty() || tx.fee_asset.0.is_empty() {
+            return Err(FeeMaximizerError::EmptyAsset);
         }
         if &tx.from == SYSTEM_ADDRESS.deref() || &tx.to == SYSTEM_ADDRESS.deref() {
-            return Err("cannot send to or from system address".to_string());
+            return Err(FeeMaximizerError::SystemAddress);
         }
-        if tx.amount >= 0.0 && tx.fee >= 0.0 {
-            self.0.push(tx);
+        if !tx.amount.is_negative() && !tx.fee.is_negative() {
+            self.transactions.push(tx);
             Ok(())
         } else {
-            Err("amount and fee must be non-negative".to_string())
+            Err(FeeMaximizerError::NegativeAmount)
         }
     }
+
+    /// Get the transactions of the request.
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// Whether the request must be executed fully or not at all.
+    pub fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+
+    /// Set whether the request must be executed fully or not at all.
+    pub fn set_atomic(&mut self, atomic: bool) {
+        self.atomic = atomic;
+    }
+
+    /// Get the user-supplied id of the request, if any.
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    /// Set a user-supplied id, reported in the solution along with the request index.
+    pub fn set_id(&mut self, id: u64) {
+        self.id = Some(id);
+    }
 }
 
 impl FeeMaximizer {
@@ -78,106 +284,441 @@ impl FeeMaximizer {
     pub fn init_empty() -> Self {
         let mut balance = HashMap::new();
         // initialize system address with 0 balance
-        balance.insert(SYSTEM_ADDRESS.clone(), 0.0);
+        balance.insert((SYSTEM_ADDRESS.clone(), NATIVE_ASSET.clone()), Amount::ZERO);
         Self {
             balance,
             requests: Vec::new(),
+            constraints: Constraints::default(),
+            capacity: BlockCapacity::default(),
+            objective: NATIVE_ASSET.clone(),
+            audit: cfg!(debug_assertions),
+            stats: None,
         }
     }
 
-    /// add balance based on a csv file with two columns (User and balance)
+    /// add balance based on a csv file with columns User, Asset and Balance,
+    /// where the Asset column is optional (native asset by default).
     ///
-    /// Must use ',' as delimiter.
-    ///
-    /// Note: set `has_headers = true` if the csv has header 'User,Balance'.
+    /// See [`add_balance_from_reader`](Self::add_balance_from_reader).
     pub fn add_balance_from_csv<P: AsRef<Path>>(
         &mut self,
         balance_csv: P,
         has_headers: bool,
-    ) -> io::Result<()> {
+    ) -> Result<(), FeeMaximizerError> {
+        self.add_balance_from_reader(File::open(balance_csv)?, has_headers)
+    }
+
+    /// add balance from csv data with columns User, Asset and Balance,
+    /// where the Asset column is optional (native asset by default).
+    ///
+    /// Any reader works, e.g. an in-memory `&[u8]` buffer or stdin.
+    ///
+    /// Must use ',' as delimiter.
+    ///
+    /// Note: set `has_headers = true` if the csv has header 'User,Balance'
+    /// or 'User,Asset,Balance'. Without headers, the columns must be 'User,Balance',
+    /// optionally followed by an 'Asset' column.
+    pub fn add_balance_from_reader<R: Read>(
+        &mut self,
+        reader: R,
+        has_headers: bool,
+    ) -> Result<(), FeeMaximizerError> {
         let mut csv_reader = csv::ReaderBuilder::new()
             .has_headers(has_headers)
+            .flexible(!has_headers)
             .trim(Trim::All)
-            .from_path(balance_csv)?;
-
-        #[derive(Deserialize)]
-        struct BalanceEntry {
-            #[serde(rename = "User")]
-            address: String,
-            #[serde(rename = "Balance")]
-            amount: f64,
-        }
-
-        for balance in csv_reader.deserialize() {
-            let balance: BalanceEntry = balance?;
-            if balance.address.is_empty() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "address cannot be empty",
-                ));
-            }
-            if balance.amount < 0.0 {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "balance must be non-negative",
-                ));
+            .from_reader(reader);
+
+        let headers = has_headers
+            .then(|| csv_reader.headers().cloned())
+            .transpose()?;
+        for record in csv_reader.records() {
+            let record = record?;
+            let balance: BalanceEntry = if let Some(headers) = &headers {
+                record.deserialize(Some(headers))?
+            } else {
+                let HeaderlessBalanceEntry(address, amount, asset) = record.deserialize(None)?;
+                BalanceEntry {
+                    address,
+                    asset,
+                    amount,
+                }
+            };
+            let asset = match balance.asset {
+                Some(asset) if !asset.is_empty() => Asset(asset.into_bytes()),
+                _ => NATIVE_ASSET.clone(),
+            };
+            self.add_balance(
+                Address::from_string(balance.address)?,
+                asset,
+                balance.amount,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Set the balance of an address in an asset, replacing any previous balance.
+    pub fn set_balance(
+        &mut self,
+        address: Address,
+        asset: Asset,
+        amount: Amount,
+    ) -> Result<(), FeeMaximizerError> {
+        if amount.is_negative() {
+            return Err(FeeMaximizerError::NegativeBalance);
+        }
+        self.balance.insert((address, asset), amount);
+        Ok(())
+    }
+
+    /// Add to the balance of an address in an asset (zero if it holds none),
+    /// like every row of a balance csv.
+    pub fn add_balance(
+        &mut self,
+        address: Address,
+        asset: Asset,
+        amount: Amount,
+    ) -> Result<(), FeeMaximizerError> {
+        if amount.is_negative() {
+            return Err(FeeMaximizerError::NegativeBalance);
+        }
+        let entry = self.balance.entry((address, asset)).or_insert(Amount::ZERO);
+        *entry = entry
+            .checked_add(amount)
+            .ok_or(FeeMaximizerError::AmountOverflow)?;
+        Ok(())
+    }
+
+    /// Remove the balance of an address in an asset, and return it if any.
+    pub fn remove_balance(&mut self, address: &Address, asset: &Asset) -> Option<Amount> {
+        self.balance.remove(&(address.clone(), asset.clone()))
+    }
+
+    /// add requests based on a csv file with columns request, from, to, amount and fee,
+    /// and the optional columns atomic, weight, asset and fee_asset.
+    ///
+    /// See [`add_requests_from_reader`](Self::add_requests_from_reader).
+    pub fn add_requests_from_csv<P: AsRef<Path>>(
+        &mut self,
+        requests_csv: P,
+        has_headers: bool,
+    ) -> Result<(), FeeMaximizerError> {
+        self.add_requests_from_reader(File::open(requests_csv)?, has_headers)
+    }
+
+    /// add requests from csv data with columns request, from, to, amount and fee,
+    /// and the optional columns atomic, weight, asset and fee_asset.
+    ///
+    /// Any reader works, e.g. an in-memory `&[u8]` buffer or stdin.
+    ///
+    /// Rows with the same `request` value form one request, whose id is this value.
+    /// Requests are added in the order of their first row, and transactions
+    /// in the order of their rows. A request is atomic if any of its rows is.
+    /// Nothing is added if any row is invalid.
+    ///
+    /// Must use ',' as delimiter.
+    ///
+    /// Note: set `has_headers = true` if the csv has a header row.
+    /// Without headers, the columns must be in the order above.
+    pub fn add_requests_from_reader<R: Read>(
+        &mut self,
+        reader: R,
+        has_headers: bool,
+    ) -> Result<(), FeeMaximizerError> {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .has_headers(has_headers)
+            .trim(Trim::All)
+            .from_reader(reader);
+
+        let asset = |asset: Option<String>| match asset {
+            Some(asset) if !asset.is_empty() => Asset(asset.into_bytes()),
+            _ => NATIVE_ASSET.clone(),
+        };
+        let mut requests: Vec<Request> = Vec::new();
+        let mut positions = HashMap::new();
+        for entry in csv_reader.deserialize() {
+            let entry: RequestEntry = entry?;
+            let position = *positions.entry(entry.request).or_insert_with(|| {
+                let mut request = Request::init_empty();
+                request.set_id(entry.request);
+                requests.push(request);
+                requests.len() - 1
+            });
+            let request = &mut requests[position];
+            request.atomic |= entry.atomic;
+            request.add_transaction(
+                Transaction::new(
+                    Address::from_string(entry.from)?,
+                    Address::from_string(entry.to)?,
+                    entry.amount,
+                    entry.fee,
+                )
+                .with_asset(asset(entry.asset))
+                .with_fee_asset(asset(entry.fee_asset))
+                .with_weight(entry.weight),
+            )?;
+        }
+        self.requests.extend(requests);
+        Ok(())
+    }
+
+    /// write the balance as a csv with header 'User,Asset,Balance',
+    /// which can be read back by `add_balance_from_csv`.
+    ///
+    /// Rows are sorted by address and asset.
+    pub fn write_balance_csv<W: Write>(&self, writer: W) -> Result<(), FeeMaximizerError> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        for ((address, asset), amount) in self.sorted_balance() {
+            csv_writer.serialize(BalanceEntry {
+                address: address.to_string(),
+                asset: Some(asset.to_string()),
+                amount: *amount,
+            })?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    /// write the requests as a csv with all the columns read by `add_requests_from_reader`.
+    ///
+    /// The `request` column is the id of the request, or its index if it has none.
+    /// Requests without transactions are not written.
+    pub fn write_requests_csv<W: Write>(&self, writer: W) -> Result<(), FeeMaximizerError> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        for (index, request) in self.requests.iter().enumerate() {
+            for tx in &request.transactions {
+                csv_writer.serialize(RequestEntry {
+                    request: request.id.unwrap_or(index as u64),
+                    from: tx.from.to_string(),
+                    to: tx.to.to_string(),
+                    amount: tx.amount,
+                    fee: tx.fee,
+                    atomic: request.atomic,
+                    weight: tx.weight,
+                    asset: Some(tx.asset.to_string()),
+                    fee_asset: Some(tx.fee_asset.to_string()),
+                })?;
             }
-            self.balance
-                .entry(Address(balance.address.into_bytes()))
-                .and_modify(|bal| *bal += balance.amount)
-                .or_insert(balance.amount);
         }
+        csv_writer.flush()?;
         Ok(())
     }
 
-    /// Add a request to the optimizer.
-    pub fn add_request(&mut self, req: &Request) {
+    /// Add a request to the optimizer, and return its index.
+    pub fn add_request(&mut self, req: &Request) -> usize {
         self.requests.push(req.clone());
+        self.requests.len() - 1
     }
 
-    /// Solve the problem and return a list of transactions.
+    /// Get the requests in the order they are added.
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    /// Request `request` may only be included if request `depends_on`
+    /// is included and executed before it.
+    ///
+    /// Requests are referred to by the index returned by `add_request`.
+    /// Indices and cycles are checked when solving.
+    pub fn add_dependency(&mut self, request: usize, depends_on: usize) {
+        self.constraints.add_dependency(request, depends_on);
+    }
+
+    /// Requests `a` and `b` cannot be both included.
+    ///
+    /// Requests are referred to by the index returned by `add_request`.
+    pub fn add_conflict(&mut self, a: usize, b: usize) {
+        self.constraints.add_conflict(a, b);
+    }
+
+    /// Get the constraints between requests.
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    /// Limit the number of transactions and/or the total weight of the solution.
+    pub fn set_block_capacity(&mut self, capacity: BlockCapacity) {
+        self.capacity = capacity;
+    }
+
+    /// Get the block capacity.
+    pub fn block_capacity(&self) -> BlockCapacity {
+        self.capacity
+    }
+
+    /// Maximize the fees paid in `asset` (the native asset by default).
+    ///
+    /// Fees paid in other assets are still collected by the system address,
+    /// but do not count towards the objective.
+    pub fn set_objective_asset(&mut self, asset: Asset) {
+        self.objective = asset;
+    }
+
+    /// Get the asset whose fees are maximized.
+    pub fn objective_asset(&self) -> &Asset {
+        &self.objective
+    }
+
+    /// Check the invariants of every solution: the total supply of every asset
+    /// (including the system balance) is unchanged, no balance is negative,
+    /// and the system balance of every asset grows by the fees paid in it.
+    ///
+    /// Violations are reported in [`Solution::violations`] instead of panicking,
+    /// and the balance is then left unchanged. Auditing is on by default in debug builds.
+    pub fn set_audit(&mut self, audit: bool) {
+        self.audit = audit;
+    }
+
+    /// Get whether every solution is audited.
+    pub fn is_audited(&self) -> bool {
+        self.audit
+    }
+
+    /// Solve the problem using the genetic algorithm.
     pub fn solve(
         &mut self,
         population_size: usize,
         selection_size: usize,
         num_generation: usize,
-    ) -> Result<Vec<Transaction>, String> {
-        // basic check for parameters
-        if selection_size >= population_size {
-            return Err(format!(
-                "selection size should be smaller than population size"
-            ));
-        }
-        // short circuit if there is no request.
-        if self.requests.is_empty() {
-            return Ok(Vec::new());
-        }
-        let (tx_list, new_bal) = maximize_fee(
-            &self.balance,
-            &self.requests,
+    ) -> Result<Solution, FeeMaximizerError> {
+        self.solve_with(&GeneticSolver::new(SolverConfig {
             population_size,
             selection_size,
             num_generation,
-        );
-        self.balance = new_bal;
-        Ok(tx_list)
+            ..SolverConfig::default()
+        }))
+    }
+
+    /// Solve the problem with any [`Solver`].
+    pub fn solve_with(&mut self, solver: &dyn Solver) -> Result<Solution, FeeMaximizerError> {
+        self.solve_warm(solver, None)
     }
 
-    /// Get a read-only reference of balance
-    pub fn balance(&self) -> &HashMap<Address, f64> {
+    /// Solve the problem, starting the genetic search from previously learned weights.
+    pub(crate) fn solve_warm(
+        &mut self,
+        solver: &dyn Solver,
+        warm_start: Option<&WarmStart>,
+    ) -> Result<Solution, FeeMaximizerError> {
+        solver.validate()?;
+        self.constraints.validate(self.requests.len())?;
+        // short circuit if there is no request.
+        if self.requests.is_empty() {
+            self.stats = Some(SolverStats {
+                solver: solver.name().to_string(),
+                ..SolverStats::default()
+            });
+            return Ok(Solution::default());
+        }
+        let problem = Problem {
+            balance: &self.balance,
+            requests: &self.requests,
+            constraints: &self.constraints,
+            capacity: self.capacity,
+            objective: &self.objective,
+            warm_start,
+        };
+        let output = solver.solve(&problem)?;
+        let invalid = |reason: String| FeeMaximizerError::InvalidSolution {
+            solver: solver.name().to_string(),
+            reason,
+        };
+        let mut solution =
+            Solution::from_selected(&self.requests, &output.selected, &self.objective)
+                .map_err(invalid)?;
+        let usage = solution.usage;
+        if !self.capacity.fits(usage) {
+            return Err(FeeMaximizerError::CapacityExceeded {
+                solver: solver.name().to_string(),
+                usage,
+            });
+        }
+        // a custom solver may break the balance, atomicity or constraints.
+        verify::verify_problem(&problem, &solution).map_err(|e| invalid(e.to_string()))?;
+        if self.audit {
+            solution.violations = audit::audit(&self.balance, &output.balance, &solution);
+        }
+        // a balance breaking the invariants is not committed.
+        if solution.violations.is_empty() {
+            self.balance = output.balance;
+        }
+        self.stats = Some(SolverStats {
+            usage,
+            best_fee: solution.total_fee,
+            ..output.stats
+        });
+        Ok(solution)
+    }
+
+    /// Get the statistics of the last solve, if any.
+    pub fn stats(&self) -> Option<&SolverStats> {
+        self.stats.as_ref()
+    }
+
+    /// Get a read-only reference of balance, keyed by address and asset
+    ///
+    /// Iterate over it to read the balance of every address, e.g. after solving.
+    pub fn balance(&self) -> &Balance {
         &self.balance
     }
 
-    /// Get the balance of an address
+    /// Get the balance entries sorted by address and asset, for a deterministic output.
+    pub(crate) fn sorted_balance(&self) -> Vec<(&(Address, Asset), &Amount)> {
+        let mut balance = self.balance.iter().collect::<Vec<_>>();
+        balance.sort_by(|(a, _), (b, _)| (&a.0 .0, &a.1 .0).cmp(&(&b.0 .0, &b.1 .0)));
+        balance
+    }
+
+    /// Get the native asset balance of an address
+    ///
+    /// return -1 minor unit if the address is not found
+    pub fn get_balance(&self, address: &Address) -> Amount {
+        self.get_asset_balance(address, &NATIVE_ASSET)
+    }
+
+    /// Get the balance of an address in an asset
     ///
-    /// return -1.0 if the address is not found
-    pub fn get_balance(&self, address: &Address) -> f64 {
-        *self.balance.get(address).unwrap_or(&-1.0)
+    /// return -1 minor unit if the address does not hold the asset
+    pub fn get_asset_balance(&self, address: &Address, asset: &Asset) -> Amount {
+        *self
+            .balance
+            .get(&(address.clone(), asset.clone()))
+            .unwrap_or(&Amount::from_minor(-1))
     }
 }
 
 impl Display for Address {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&*String::from_utf8_lossy(&self.0))
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl Display for Asset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_string(String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Asset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Asset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_string(String::deserialize(deserializer)?).map_err(de::Error::custom)
     }
 }
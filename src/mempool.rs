@@ -0,0 +1,299 @@
+//! This file defines the mempool mode: requests are added, replaced or removed by id
+//! between blocks, and every solve commits its block and warm-starts the next one.
+use crate::{
+    Address, Amount, Asset, Balance, Constraints, FeeMaximizer, FeeMaximizerError, GeneticSolver,
+    Request, Solution, Solver, SolverConfig, SolverStats, WarmStart,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A pool of pending requests solved block after block.
+///
+/// Requests must have a unique id, by which they are replaced, removed and constrained.
+/// Solving commits the block: the balance is updated, executed transactions are removed
+/// from their requests, and requests conflicting with an included request are evicted.
+/// The weights learned by the genetic solver are kept to warm-start the next solve.
+///
+/// The indices in a solution refer to the order of [`requests`](Self::requests)
+/// at the time of solving.
+#[derive(Clone)]
+pub struct Mempool {
+    /// balance, capacity, objective, and the pending requests in insertion order.
+    fee_maximizer: FeeMaximizer,
+    /// position of every pending request by id.
+    positions: HashMap<u64, usize>,
+    /// (request, depends_on) pairs of request ids.
+    dependencies: Vec<(u64, u64)>,
+    /// pairs of mutually exclusive request ids.
+    conflicts: Vec<(u64, u64)>,
+    warm_start: WarmStart,
+}
+
+impl Mempool {
+    /// Create a mempool from a fee maximizer, keeping its balance, capacity,
+    /// objective, audit mode, requests and constraints.
+    ///
+    /// Every request must have a unique id.
+    pub fn new(mut fee_maximizer: FeeMaximizer) -> Result<Self, FeeMaximizerError> {
+        let requests = std::mem::take(&mut fee_maximizer.requests);
+        let constraints = std::mem::take(&mut fee_maximizer.constraints);
+        constraints.validate(requests.len())?;
+        let mut mempool = Self {
+            fee_maximizer,
+            positions: HashMap::with_capacity(requests.len()),
+            dependencies: Vec::new(),
+            conflicts: Vec::new(),
+            warm_start: WarmStart::default(),
+        };
+        for request in &requests {
+            mempool.add(request)?;
+        }
+        // ids are checked above.
+        let id = |index: usize| requests[index].id.unwrap();
+        mempool.dependencies = constraints
+            .dependencies()
+            .iter()
+            .map(|(r, d)| (id(*r), id(*d)))
+            .collect();
+        mempool.conflicts = constraints
+            .conflicts()
+            .iter()
+            .map(|(a, b)| (id(*a), id(*b)))
+            .collect();
+        Ok(mempool)
+    }
+
+    /// Add a pending request, which must have an id not already in the mempool.
+    pub fn add(&mut self, request: &Request) -> Result<(), FeeMaximizerError> {
+        let id = request.id.ok_or(FeeMaximizerError::MissingRequestId)?;
+        if self.positions.contains_key(&id) {
+            return Err(FeeMaximizerError::DuplicateRequestId(id));
+        }
+        let position = self.fee_maximizer.add_request(request);
+        self.positions.insert(id, position);
+        Ok(())
+    }
+
+    /// Replace the pending request with the same id, and return the replaced request.
+    ///
+    /// The request keeps its position and constraints, but its learned weights are dropped.
+    pub fn replace(&mut self, request: &Request) -> Result<Request, FeeMaximizerError> {
+        let id = request.id.ok_or(FeeMaximizerError::MissingRequestId)?;
+        let position = self
+            .position(id)
+            .ok_or(FeeMaximizerError::UnknownRequestId(id))?;
+        self.warm_start.requests.remove(&id);
+        Ok(std::mem::replace(
+            &mut self.fee_maximizer.requests[position],
+            request.clone(),
+        ))
+    }
+
+    /// Remove a pending request, along with its constraints and learned weights.
+    pub fn remove(&mut self, id: u64) -> Option<Request> {
+        let position = self.positions.remove(&id)?;
+        self.drop_constraints(&HashSet::from([id]));
+        self.warm_start.requests.remove(&id);
+        // the requests after it move one position back.
+        for p in self.positions.values_mut() {
+            if *p > position {
+                *p -= 1;
+            }
+        }
+        Some(self.fee_maximizer.requests.remove(position))
+    }
+
+    /// Request `request` may only be included if request `depends_on`
+    /// is included and executed before it, in the same block.
+    pub fn add_dependency(
+        &mut self,
+        request: u64,
+        depends_on: u64,
+    ) -> Result<(), FeeMaximizerError> {
+        self.check_ids(&[request, depends_on])?;
+        self.dependencies.push((request, depends_on));
+        Ok(())
+    }
+
+    /// Requests `a` and `b` cannot be both included: once one of them is,
+    /// the other is evicted.
+    pub fn add_conflict(&mut self, a: u64, b: u64) -> Result<(), FeeMaximizerError> {
+        self.check_ids(&[a, b])?;
+        self.conflicts.push((a, b));
+        Ok(())
+    }
+
+    /// Get the pending requests in the order they are added.
+    pub fn requests(&self) -> &[Request] {
+        &self.fee_maximizer.requests
+    }
+
+    /// Get a pending request by id.
+    pub fn get(&self, id: u64) -> Option<&Request> {
+        self.position(id).map(|p| &self.fee_maximizer.requests[p])
+    }
+
+    /// Number of pending requests.
+    pub fn len(&self) -> usize {
+        self.fee_maximizer.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fee_maximizer.requests.is_empty()
+    }
+
+    /// Set the balance of an address in an asset, e.g. after an external deposit.
+    pub fn set_balance(
+        &mut self,
+        address: Address,
+        asset: Asset,
+        amount: Amount,
+    ) -> Result<(), FeeMaximizerError> {
+        self.fee_maximizer.set_balance(address, asset, amount)
+    }
+
+    /// Add to the balance of an address in an asset.
+    pub fn add_balance(
+        &mut self,
+        address: Address,
+        asset: Asset,
+        amount: Amount,
+    ) -> Result<(), FeeMaximizerError> {
+        self.fee_maximizer.add_balance(address, asset, amount)
+    }
+
+    /// Get the balance after the committed blocks.
+    pub fn balance(&self) -> &Balance {
+        self.fee_maximizer.balance()
+    }
+
+    /// Get the weights learned by the last genetic solve.
+    pub fn warm_start(&self) -> &WarmStart {
+        &self.warm_start
+    }
+
+    /// Get the statistics of the last solve, if any.
+    pub fn stats(&self) -> Option<&SolverStats> {
+        self.fee_maximizer.stats()
+    }
+
+    /// Solve the next block with the genetic algorithm, see [`solve_with`](Self::solve_with).
+    pub fn solve(
+        &mut self,
+        population_size: usize,
+        selection_size: usize,
+        num_generation: usize,
+    ) -> Result<Solution, FeeMaximizerError> {
+        self.solve_with(&GeneticSolver::new(SolverConfig {
+            population_size,
+            selection_size,
+            num_generation,
+            ..SolverConfig::default()
+        }))
+    }
+
+    /// Solve the next block and commit it.
+    ///
+    /// The genetic solver starts from the weights learned by the previous genetic solve.
+    /// A solution with audit violations is returned without being committed.
+    pub fn solve_with(&mut self, solver: &dyn Solver) -> Result<Solution, FeeMaximizerError> {
+        let index = &self.positions;
+        let mut constraints = Constraints::default();
+        for (request, depends_on) in &self.dependencies {
+            constraints.add_dependency(index[request], index[depends_on]);
+        }
+        for (a, b) in &self.conflicts {
+            constraints.add_conflict(index[a], index[b]);
+        }
+        self.fee_maximizer.constraints = constraints;
+        let solution = self
+            .fee_maximizer
+            .solve_warm(solver, Some(&self.warm_start))?;
+        if !solution.violations.is_empty() {
+            return Ok(solution);
+        }
+        if let Some(warm_start) = self.stats().and_then(|s| s.warm_start.clone()) {
+            self.warm_start = warm_start;
+        }
+        self.commit(&solution);
+        Ok(solution)
+    }
+
+    /// Remove the executed transactions of a committed solution.
+    fn commit(&mut self, solution: &Solution) {
+        let mut executed: HashMap<usize, Vec<usize>> = HashMap::new();
+        for e in &solution.executed {
+            executed.entry(e.request).or_default().push(e.index);
+        }
+        let included = executed
+            .keys()
+            .map(|r| self.fee_maximizer.requests[*r].id.unwrap())
+            .collect::<HashSet<_>>();
+        for (req, mut indices) in executed {
+            indices.sort_unstable_by(|a, b| b.cmp(a));
+            let request = &mut self.fee_maximizer.requests[req];
+            let weights = self.warm_start.requests.get_mut(&request.id.unwrap());
+            let selection = weights
+                .map(|w| &mut w.selection)
+                .filter(|s| s.len() == request.transactions.len());
+            if let Some(selection) = selection {
+                for index in &indices {
+                    selection.remove(*index);
+                }
+            }
+            for index in indices {
+                request.transactions.remove(index);
+            }
+        }
+        let mut removed = self
+            .conflicts
+            .iter()
+            .filter_map(
+                |(a, b)| match (included.contains(a), included.contains(b)) {
+                    (true, false) => Some(*b),
+                    (false, true) => Some(*a),
+                    _ => None,
+                },
+            )
+            .collect::<HashSet<_>>();
+        removed.extend(
+            self.requests()
+                .iter()
+                .filter(|r| r.transactions.is_empty())
+                .map(|r| r.id.unwrap()),
+        );
+        self.fee_maximizer
+            .requests
+            .retain(|r| !removed.contains(&r.id.unwrap()));
+        self.positions = self
+            .requests()
+            .iter()
+            .enumerate()
+            .map(|(position, r)| (r.id.unwrap(), position))
+            .collect();
+        self.warm_start
+            .requests
+            .retain(|id, _| !removed.contains(id));
+        // constraints on included requests are settled by this block.
+        removed.extend(included);
+        self.drop_constraints(&removed);
+    }
+
+    /// Drop the constraints involving these requests.
+    fn drop_constraints(&mut self, ids: &HashSet<u64>) {
+        self.dependencies
+            .retain(|(r, d)| !ids.contains(r) && !ids.contains(d));
+        self.conflicts
+            .retain(|(a, b)| !ids.contains(a) && !ids.contains(b));
+    }
+
+    fn check_ids(&self, ids: &[u64]) -> Result<(), FeeMaximizerError> {
+        match ids.iter().find(|id| self.position(**id).is_none()) {
+            Some(id) => Err(FeeMaximizerError::UnknownRequestId(*id)),
+            None => Ok(()),
+        }
+    }
+
+    fn position(&self, id: u64) -> Option<usize> {
+        self.positions.get(&id).copied()
+    }
+}
@@ -0,0 +1,141 @@
+//! This file defines the solution returned by the fee maximizer,
+//! which keeps track of the request and position of every transaction.
+use crate::{Amount, Asset, BlockUsage, Request, Transaction, Violation};
+use serde::{Deserialize, Serialize};
+
+/// An executed transaction with its provenance.
+///
+/// In JSON, the fields of the transaction are inlined.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExecutedTransaction {
+    /// index of the request (i.e., the order in which it is added to the optimizer).
+    pub request: usize,
+    /// user-supplied id of the request, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<u64>,
+    /// position of the transaction within the request.
+    pub index: usize,
+    #[serde(flatten)]
+    pub transaction: Transaction,
+}
+
+/// A transaction that is not executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedTransaction {
+    /// index of the request (i.e., the order in which it is added to the optimizer).
+    pub request: usize,
+    /// user-supplied id of the request, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<u64>,
+    /// position of the transaction within the request.
+    pub index: usize,
+}
+
+/// The result of solving.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Solution {
+    /// executed transactions in execution order.
+    pub executed: Vec<ExecutedTransaction>,
+    /// transactions that are not executed, ordered by request and position.
+    pub rejected: Vec<RejectedTransaction>,
+    /// indices of the requests none of whose transactions are executed.
+    pub rejected_requests: Vec<usize>,
+    /// total fee collected in the objective asset.
+    pub total_fee: Amount,
+    /// block capacity consumed by the executed transactions.
+    #[serde(default)]
+    pub usage: BlockUsage,
+    /// invariants broken by the solution, only checked in audit mode
+    /// (see [`FeeMaximizer::set_audit`](crate::FeeMaximizer::set_audit)).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+}
+
+impl Solution {
+    /// Build a solution from the selected (request, transaction) indices in execution order.
+    ///
+    /// Return an error if an index is out of range or selected twice.
+    pub(crate) fn from_selected(
+        requests: &[Request],
+        selected: &[(usize, usize)],
+        objective: &Asset,
+    ) -> Result<Self, String> {
+        let mut is_executed = requests
+            .iter()
+            .map(|r| vec![false; r.transactions.len()])
+            .collect::<Vec<_>>();
+        let mut executed = Vec::with_capacity(selected.len());
+        let mut total_fee = Amount::ZERO;
+        for (request, index) in selected.iter().copied() {
+            match is_executed.get_mut(request).and_then(|r| r.get_mut(index)) {
+                Some(false) => is_executed[request][index] = true,
+                Some(true) => {
+                    return Err(format!(
+                        "transaction {index} of request {request} is selected twice"
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "transaction {index} of request {request} does not exist"
+                    ))
+                }
+            }
+            let transaction = requests[request].transactions[index].clone();
+            total_fee = total_fee
+                .checked_add(transaction.fee_in(objective))
+                .ok_or_else(|| "total fee overflow".to_string())?;
+            executed.push(ExecutedTransaction {
+                request,
+                request_id: requests[request].id,
+                index,
+                transaction,
+            });
+        }
+        let mut rejected = Vec::new();
+        let mut rejected_requests = Vec::new();
+        for (request, flags) in is_executed.iter().enumerate() {
+            if !flags.contains(&true) {
+                rejected_requests.push(request);
+            }
+            rejected.extend(
+                flags
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, executed)| !**executed)
+                    .map(|(index, _)| RejectedTransaction {
+                        request,
+                        request_id: requests[request].id,
+                        index,
+                    }),
+            );
+        }
+        let usage = BlockUsage::of(executed.iter().map(|e| &e.transaction));
+        Ok(Self {
+            executed,
+            rejected,
+            rejected_requests,
+            total_fee,
+            usage,
+            violations: Vec::new(),
+        })
+    }
+
+    /// Number of executed transactions.
+    pub fn len(&self) -> usize {
+        self.executed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.executed.is_empty()
+    }
+
+    /// Iterate over the executed transactions in execution order.
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+        self.executed.iter().map(|e| &e.transaction)
+    }
+
+    /// Get the executed transactions in execution order, dropping the provenance.
+    pub fn into_transactions(self) -> Vec<Transaction> {
+        self.executed.into_iter().map(|e| e.transaction).collect()
+    }
+}
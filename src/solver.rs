@@ -0,0 +1,513 @@
+//! This file defines the `Solver` trait and the built-in solving strategies.
+use crate::algo::maximize_fee;
+use crate::exact::maximize_fee_exact;
+use crate::greedy::maximize_fee_greedy;
+use crate::{
+    Amount, Asset, Balance, BlockCapacity, BlockUsage, Constraints, FeeMaximizerError, Request,
+    Transaction,
+};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Names of the built-in solvers, accepted by [`solver_from_name`].
+pub const SOLVER_NAMES: [&str; 3] = ["genetic", "greedy", "exact"];
+
+/// The input of a solver.
+pub struct Problem<'a> {
+    /// initial balance of every address (including the system address) in every asset.
+    pub balance: &'a Balance,
+    pub requests: &'a [Request],
+    /// dependencies and conflicts between requests.
+    pub constraints: &'a Constraints,
+    /// maximum number of transactions and total weight of the solution.
+    pub capacity: BlockCapacity,
+    /// the asset whose fees are maximized.
+    pub objective: &'a Asset,
+    /// weights learned by a previous genetic solve, to start the search from (genetic).
+    pub warm_start: Option<&'a WarmStart>,
+}
+
+/// The output of a solver.
+pub struct SolverOutput {
+    /// selected (request index, transaction index) pairs in execution order.
+    pub selected: Vec<(usize, usize)>,
+    /// balance after executing the selected transactions.
+    pub balance: Balance,
+    pub stats: SolverStats,
+}
+
+/// Statistics reported by a solver.
+#[derive(Clone, Debug, Default)]
+pub struct SolverStats {
+    /// name of the solver that produced the result.
+    pub solver: String,
+    /// number of candidate solutions (or search nodes) evaluated.
+    pub evaluations: usize,
+    /// wall-clock time spent on solving.
+    pub elapsed: Duration,
+    /// whether the solution is proven to be optimal.
+    pub optimal: bool,
+    /// block capacity consumed by the selected transactions.
+    pub usage: BlockUsage,
+    /// why the solver stopped.
+    pub stop_reason: StopReason,
+    /// number of generations run by the genetic solver, 0 for the other solvers.
+    pub generations: usize,
+    /// total fee of the solution in the objective asset, set by the fee maximizer.
+    pub best_fee: Amount,
+    /// weights learned by the genetic solver, `None` for the other solvers.
+    pub warm_start: Option<WarmStart>,
+}
+
+/// Why a solver stopped searching.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StopReason {
+    /// the search ran to the end (e.g. all the generations).
+    #[default]
+    Completed,
+    /// the time budget ran out.
+    TimeBudget,
+    /// the best fee did not improve for `patience` generations.
+    Converged,
+    /// the search was cancelled with a [`CancellationToken`].
+    Cancelled,
+}
+
+/// Weights learned by the genetic solver, to warm-start a later solve
+/// on an overlapping set of requests (see [`Mempool`](crate::Mempool)).
+///
+/// Requests are matched by id, requests without id are not learned.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WarmStart {
+    pub(crate) requests: HashMap<u64, RequestWeights>,
+}
+
+/// The weights learned for a request, as fractions between 0 and 1.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RequestWeights {
+    /// how often the best solutions select each transaction.
+    pub(crate) selection: Vec<f64>,
+    /// how early the best solutions execute the request.
+    pub(crate) order: f64,
+}
+
+impl WarmStart {
+    /// How often the best solutions selected each transaction of a request, between 0 and 1.
+    pub fn selection_weights(&self, request_id: u64) -> Option<&[f64]> {
+        self.requests
+            .get(&request_id)
+            .map(|w| w.selection.as_slice())
+    }
+
+    /// How early the best solutions executed a request, between 0 (last) and 1 (first).
+    pub fn request_order_weight(&self, request_id: u64) -> Option<f64> {
+        self.requests.get(&request_id).map(|w| w.order)
+    }
+
+    /// Number of requests with learned weights.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+/// The progress of the genetic solver, reported after every generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// index of the generation, starting at 0.
+    pub generation: usize,
+    /// best fee in the objective asset found so far.
+    pub best_fee: Amount,
+    /// mean fee of the feasible solutions of the generation.
+    pub mean_fee: Amount,
+    /// fraction of the solutions of the generation that are feasible.
+    pub feasible_fraction: f64,
+}
+
+/// A callback invoked with the [`Progress`] after every generation,
+/// on the thread calling the solver.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+/// A token to stop a solver from another thread (or from a progress callback).
+///
+/// The solver stops before starting its next generation,
+/// and returns the best solution found so far.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+/// A strategy to select and order transactions.
+///
+/// Implement this trait to plug custom strategies into
+/// [`FeeMaximizer::solve_with`](crate::FeeMaximizer::solve_with).
+pub trait Solver: Send + Sync {
+    /// name of the solver.
+    fn name(&self) -> &str;
+
+    /// Select and order transactions from `problem.requests`.
+    ///
+    /// Custom solvers may report failures with [`FeeMaximizerError::Solver`].
+    /// A selection that cannot be paid or breaks a constraint is rejected with
+    /// [`FeeMaximizerError::InvalidSolution`] before being committed.
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError>;
+
+    /// Check the parameters of the solver, before solving any problem (even an empty one).
+    fn validate(&self) -> Result<(), FeeMaximizerError> {
+        Ok(())
+    }
+}
+
+impl Problem<'_> {
+    /// Iterate over the selected transactions given (request, transaction) indices.
+    ///
+    /// Panics if an index is out of range.
+    pub fn selected_transactions<'b>(
+        &'b self,
+        selected: &'b [(usize, usize)],
+    ) -> impl Iterator<Item = &'b Transaction> {
+        selected
+            .iter()
+            .map(|(req, tx)| &self.requests[*req].transactions[*tx])
+    }
+}
+
+/// Parameters shared by the built-in solvers.
+///
+/// Each solver only reads the parameters relevant to it.
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    /// number of individuals in the population (genetic).
+    pub population_size: usize,
+    /// number of individuals selected for the next generation, at least 1 (genetic).
+    pub selection_size: usize,
+    /// number of generations (genetic).
+    pub num_generation: usize,
+    /// rng seed for reproducible results, random if `None` (genetic).
+    pub seed: Option<u64>,
+    /// maximum wall-clock time, unlimited if `None` (genetic).
+    ///
+    /// A generation is only started if it is expected to finish in time,
+    /// judging by the previous one. At least one generation is run.
+    pub time_budget: Option<Duration>,
+    /// stop after this many generations without improvement
+    /// of the best fee, never if `None` (genetic).
+    pub patience: Option<usize>,
+    /// called after every generation (genetic).
+    pub progress: Option<ProgressCallback>,
+    /// stop the search when cancelled (genetic).
+    pub cancellation: Option<CancellationToken>,
+    /// how agents draw their selection and order (genetic).
+    pub genetic: GeneticConfig,
+}
+
+/// How the agents of the genetic solver draw their selection and order.
+///
+/// The defaults only follow the weights learned from the previous generations.
+#[derive(Clone, Debug, Default)]
+pub struct GeneticConfig {
+    /// maps the learned weight of a transaction to its selection probability.
+    pub curve: SelectionCurve,
+    /// minimum probability of selecting a transaction, and of dropping it,
+    /// whatever its weight, between 0 and 0.5.
+    pub mutation_rate: f64,
+    /// probability that an agent ignores the learned weights,
+    /// and selects every transaction with probability 1/2.
+    pub selection_exploration: f64,
+    /// probability that an agent ignores the learned order,
+    /// and shuffles the requests uniformly.
+    pub order_exploration: f64,
+}
+
+/// Maps the learned weight of a transaction, scaled between 0 and 1,
+/// to the probability of selecting it.
+///
+/// It can be parsed from `linear`, `sigmoid`, `sigmoid:<steepness>`, `power` or `power:<exponent>`.
+#[derive(Clone, Default)]
+pub enum SelectionCurve {
+    /// the probability is the weight.
+    #[default]
+    Linear,
+    /// a logistic curve centered at 1/2 with the given steepness (10 by default),
+    /// rescaled to go from 0 to 1.
+    Sigmoid(f64),
+    /// the weight to the given power (2 by default):
+    /// exponents above 1 favour the transactions selected the most.
+    Power(f64),
+    /// a custom function, whose result is clamped between 0 and 1.
+    Custom(Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+
+/// The genetic algorithm solver.
+pub struct GeneticSolver {
+    config: SolverConfig,
+}
+
+/// A greedy solver that executes the most profitable request first.
+pub struct GreedySolver;
+
+/// An exact branch-and-bound solver, only practical for small batches.
+pub struct ExactSolver;
+
+/// Construct a built-in solver by name (see [`SOLVER_NAMES`]).
+pub fn solver_from_name(
+    name: &str,
+    config: SolverConfig,
+) -> Result<Box<dyn Solver>, FeeMaximizerError> {
+    match name {
+        "genetic" => Ok(Box::new(GeneticSolver::new(config))),
+        "greedy" => Ok(Box::new(GreedySolver)),
+        "exact" => Ok(Box::new(ExactSolver)),
+        _ => Err(FeeMaximizerError::UnknownSolver(name.to_string())),
+    }
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            population_size: 8192,
+            selection_size: 32,
+            num_generation: 50,
+            seed: None,
+            time_budget: None,
+            patience: None,
+            progress: None,
+            cancellation: None,
+            genetic: GeneticConfig::default(),
+        }
+    }
+}
+
+impl GeneticConfig {
+    fn check(&self) -> Result<(), FeeMaximizerError> {
+        let invalid =
+            |reason: &str| Err(FeeMaximizerError::InvalidSolverParams(reason.to_string()));
+        if !(0.0..=0.5).contains(&self.mutation_rate) {
+            return invalid("mutation rate should be between 0 and 0.5");
+        }
+        if !(0.0..=1.0).contains(&self.selection_exploration)
+            || !(0.0..=1.0).contains(&self.order_exploration)
+        {
+            return invalid("exploration rates should be between 0 and 1");
+        }
+        match self.curve {
+            SelectionCurve::Sigmoid(steepness) if !(steepness.is_finite() && steepness > 0.0) => {
+                invalid("sigmoid steepness should be positive")
+            }
+            SelectionCurve::Power(exponent) if !(exponent.is_finite() && exponent > 0.0) => {
+                invalid("power exponent should be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SelectionCurve {
+    pub fn custom<F: Fn(f64) -> f64 + Send + Sync + 'static>(curve: F) -> Self {
+        Self::Custom(Arc::new(curve))
+    }
+
+    /// The selection probability of a weight between 0 and 1.
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Linear => x,
+            Self::Sigmoid(steepness) => {
+                let sigmoid = |x: f64| 1.0 / (1.0 + (-steepness * (x - 0.5)).exp());
+                (sigmoid(x) - sigmoid(0.0)) / (sigmoid(1.0) - sigmoid(0.0))
+            }
+            Self::Power(exponent) => x.powf(*exponent),
+            Self::Custom(curve) => curve(x).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Debug for SelectionCurve {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => f.write_str("Linear"),
+            Self::Sigmoid(steepness) => f.debug_tuple("Sigmoid").field(steepness).finish(),
+            Self::Power(exponent) => f.debug_tuple("Power").field(exponent).finish(),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl FromStr for SelectionCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, param) = match s.split_once(':') {
+            Some((name, param)) => {
+                let param = param
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid curve parameter `{param}`"))?;
+                (name, Some(param))
+            }
+            None => (s, None),
+        };
+        match name {
+            "linear" if param.is_none() => Ok(Self::Linear),
+            "sigmoid" => Ok(Self::Sigmoid(param.unwrap_or(10.0))),
+            "power" => Ok(Self::Power(param.unwrap_or(2.0))),
+            _ => Err(format!(
+                "invalid curve `{s}`, expected linear, sigmoid[:<steepness>] or power[:<exponent>]"
+            )),
+        }
+    }
+}
+
+impl ProgressCallback {
+    pub fn new<F: Fn(&Progress) + Send + Sync + 'static>(callback: F) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, progress: &Progress) {
+        (self.0)(progress)
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the solvers using this token (or a clone of it).
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl GeneticSolver {
+    pub fn new(config: SolverConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Solver for GeneticSolver {
+    fn name(&self) -> &str {
+        "genetic"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        let config = &self.config;
+        self.validate()?;
+        let start = Instant::now();
+        let graph = problem.constraints.graph(problem.requests.len())?;
+        let run = maximize_fee(problem, &graph, config);
+        let usage = BlockUsage::of(problem.selected_transactions(&run.selected));
+        Ok(SolverOutput {
+            selected: run.selected,
+            balance: run.balance,
+            stats: SolverStats {
+                solver: self.name().to_string(),
+                evaluations: config.population_size * run.generations,
+                elapsed: start.elapsed(),
+                optimal: false,
+                usage,
+                stop_reason: run.stop_reason,
+                generations: run.generations,
+                best_fee: Amount::ZERO,
+                warm_start: Some(run.warm_start),
+            },
+        })
+    }
+
+    fn validate(&self) -> Result<(), FeeMaximizerError> {
+        let config = &self.config;
+        // basic check for parameters
+        if config.selection_size >= config.population_size {
+            return Err(FeeMaximizerError::InvalidSolverParams(
+                "selection size should be smaller than population size".to_string(),
+            ));
+        }
+        if config.patience == Some(0) {
+            return Err(FeeMaximizerError::InvalidSolverParams(
+                "patience should be positive".to_string(),
+            ));
+        }
+        config.genetic.check()
+    }
+}
+
+impl Solver for GreedySolver {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        let start = Instant::now();
+        let graph = problem.constraints.graph(problem.requests.len())?;
+        let (selected, balance, evaluations) = maximize_fee_greedy(problem, &graph);
+        let usage = BlockUsage::of(problem.selected_transactions(&selected));
+        Ok(SolverOutput {
+            selected,
+            balance,
+            stats: SolverStats {
+                solver: self.name().to_string(),
+                evaluations,
+                elapsed: start.elapsed(),
+                optimal: false,
+                usage,
+                stop_reason: StopReason::Completed,
+                generations: 0,
+                best_fee: Amount::ZERO,
+                warm_start: None,
+            },
+        })
+    }
+}
+
+impl Solver for ExactSolver {
+    fn name(&self) -> &str {
+        "exact"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        let start = Instant::now();
+        let graph = problem.constraints.graph(problem.requests.len())?;
+        let (selected, balance, evaluations) = maximize_fee_exact(problem, &graph);
+        let usage = BlockUsage::of(problem.selected_transactions(&selected));
+        Ok(SolverOutput {
+            selected,
+            balance,
+            stats: SolverStats {
+                solver: self.name().to_string(),
+                evaluations,
+                elapsed: start.elapsed(),
+                optimal: true,
+                usage,
+                stop_reason: StopReason::Completed,
+                generations: 0,
+                best_fee: Amount::ZERO,
+                warm_start: None,
+            },
+        })
+    }
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Completed => "completed",
+            Self::TimeBudget => "time budget exhausted",
+            Self::Converged => "converged",
+            Self::Cancelled => "cancelled",
+        })
+    }
+}
@@ -0,0 +1,159 @@
+//! This file defines a generator of synthetic mempools,
+//! to benchmark the solvers on instances larger than the test cases.
+use crate::{Address, Amount, FeeMaximizer, Request, Transaction, NATIVE_ASSET};
+use fastrand::Rng;
+
+/// How the initial balances are distributed among accounts.
+#[derive(Clone, Copy, Debug)]
+pub enum BalanceDistribution {
+    /// every account holds the same balance.
+    Equal(Amount),
+    /// balances are drawn uniformly between 0 and the given amount.
+    Uniform(Amount),
+    /// a few rich accounts and many poor ones: balances follow a Pareto law
+    /// with the given minimum, and a shape which is more unequal when smaller.
+    Pareto { min: Amount, shape: f64 },
+}
+
+/// Parameters of a synthetic mempool, in the native asset.
+///
+/// Requests are grouped into funding chains: the first request of a chain
+/// is sent by a funded account, and every following request spends part of
+/// what the previous one received and depends on it.
+#[derive(Clone, Debug)]
+pub struct MempoolGenerator {
+    pub num_accounts: usize,
+    pub num_requests: usize,
+    /// every request has between 1 and this many transactions.
+    pub max_transactions_per_request: usize,
+    pub balance: BalanceDistribution,
+    /// maximum number of requests in a funding chain, 1 for independent requests.
+    pub chain_depth: usize,
+    /// total amount sent by the requests of an account, relative to its balance:
+    /// above 1, the requests of an account compete for the same funds.
+    pub competition: f64,
+}
+
+impl Default for MempoolGenerator {
+    fn default() -> Self {
+        Self {
+            num_accounts: 1000,
+            num_requests: 2000,
+            max_transactions_per_request: 1,
+            balance: BalanceDistribution::Uniform(Amount::from_units(100)),
+            chain_depth: 1,
+            competition: 1.0,
+        }
+    }
+}
+
+/// Fees are drawn between these fractions of the amount.
+const FEE_RATE: (f64, f64) = (0.001, 0.05);
+
+impl MempoolGenerator {
+    /// Generate a fee maximizer with the balances and requests,
+    /// the same for the same parameters and seed.
+    ///
+    /// Panics if there are no accounts, if a request may have no transaction,
+    /// or if the balance distribution may draw a negative balance.
+    pub fn generate(&self, seed: u64) -> FeeMaximizer {
+        assert!(self.num_accounts > 0, "at least one account is needed");
+        assert!(
+            self.max_transactions_per_request > 0 && self.chain_depth > 0,
+            "requests need transactions and chains need requests"
+        );
+        let lowest = match self.balance {
+            BalanceDistribution::Equal(amount) | BalanceDistribution::Uniform(amount) => amount,
+            BalanceDistribution::Pareto { min, .. } => min,
+        };
+        assert!(!lowest.is_negative(), "balances cannot be negative");
+        let rng = Rng::with_seed(seed);
+        let mut fm = FeeMaximizer::init_empty();
+
+        let balances = (0..self.num_accounts)
+            .map(|_| self.draw_balance(&rng))
+            .collect::<Vec<_>>();
+        for (account, balance) in balances.iter().enumerate() {
+            fm.set_balance(address(account), NATIVE_ASSET.clone(), *balance)
+                .unwrap();
+        }
+
+        // split the requests into chains, and pick the sender of every chain.
+        let mut chains = Vec::new();
+        let mut remaining = self.num_requests;
+        while remaining > 0 {
+            let length = rng.usize(1..=self.chain_depth.min(remaining));
+            chains.push((rng.usize(..self.num_accounts), length));
+            remaining -= length;
+        }
+        let mut num_chains = vec![0usize; self.num_accounts];
+        chains
+            .iter()
+            .for_each(|(sender, _)| num_chains[*sender] += 1);
+
+        for (sender, length) in chains {
+            let share =
+                balances[sender].minor() as f64 * self.competition / num_chains[sender] as f64;
+            let mut sender = sender;
+            let mut budget = share * (0.5 + rng.f64());
+            let mut previous = None;
+            for _ in 0..length {
+                let (request, receiver, received) = self.draw_request(sender, budget, &rng);
+                let index = fm.add_request(&request);
+                if let Some(previous) = previous {
+                    fm.add_dependency(index, previous);
+                }
+                previous = Some(index);
+                // the next request spends part of what this one received.
+                sender = receiver;
+                budget = received * (0.5 + 0.5 * rng.f64());
+            }
+        }
+        fm
+    }
+
+    fn draw_balance(&self, rng: &Rng) -> Amount {
+        match self.balance {
+            BalanceDistribution::Equal(amount) => amount,
+            BalanceDistribution::Uniform(max) => Amount::from_minor(rng.i64(0..=max.minor())),
+            BalanceDistribution::Pareto { min, shape } => {
+                // inverse transform sampling, capped to stay far from overflow.
+                let u = 1.0 - rng.f64();
+                let factor = u.powf(-1.0 / shape).min(1e6);
+                Amount::from_minor((min.minor() as f64 * factor) as i64)
+            }
+        }
+    }
+
+    /// Draw a request from `sender` spending about `budget` minor units (fees included),
+    /// and return it with the receiver and the amount of its first transaction.
+    fn draw_request(&self, sender: usize, budget: f64, rng: &Rng) -> (Request, usize, f64) {
+        let num_transactions = rng.usize(1..=self.max_transactions_per_request);
+        let mut request = Request::init_empty();
+        let mut first = None;
+        for _ in 0..num_transactions {
+            let mut receiver = rng.usize(..self.num_accounts);
+            if receiver == sender && self.num_accounts > 1 {
+                receiver = (receiver + 1) % self.num_accounts;
+            }
+            let fee_rate = FEE_RATE.0 + (FEE_RATE.1 - FEE_RATE.0) * rng.f64();
+            let amount = budget / num_transactions as f64 / (1.0 + fee_rate);
+            let fee = (amount * fee_rate).max(1.0);
+            request
+                .add_transaction(Transaction::new(
+                    address(sender),
+                    address(receiver),
+                    Amount::from_minor(amount as i64),
+                    Amount::from_minor(fee as i64),
+                ))
+                .unwrap();
+            first.get_or_insert((receiver, amount));
+        }
+        let (receiver, received) = first.unwrap();
+        (request, receiver, received)
+    }
+}
+
+fn address(account: usize) -> Address {
+    Address(format!("account-{account:06}").into_bytes())
+}
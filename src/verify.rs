@@ -0,0 +1,188 @@
+//! This file defines the verification of a solution produced elsewhere,
+//! e.g. by another solver or an older version, by replaying its transactions.
+use crate::algo::apply_transaction;
+use crate::{
+    Amount, Asset, Balance, BlockCapacity, BlockUsage, Constraints, FeeMaximizer,
+    FeeMaximizerError, Problem, Request, Solution, Transaction, NATIVE_ASSET,
+};
+
+/// The outcome of a successful verification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verification {
+    /// total fee recomputed from the executed transactions, in the objective asset.
+    pub total_fee: Amount,
+    /// balance after executing the transactions.
+    pub balance: Balance,
+    /// block capacity consumed by the transactions.
+    pub usage: BlockUsage,
+}
+
+/// Verify a solution against an initial balance and the submitted requests,
+/// without constraints nor block capacity, counting fees in the native asset.
+///
+/// The executed transactions are replayed in order, and the first failure is returned:
+/// a transaction which is not one of the submitted requests or is executed twice,
+/// a sender unable to pay (with the shortfall), a partially executed atomic request,
+/// or a total fee different from the one claimed by the solution.
+pub fn verify(
+    balance: &Balance,
+    requests: &[Request],
+    solution: &Solution,
+) -> Result<Verification, FeeMaximizerError> {
+    verify_problem(
+        &Problem {
+            balance,
+            requests,
+            constraints: &Constraints::default(),
+            capacity: BlockCapacity::default(),
+            objective: &NATIVE_ASSET,
+            warm_start: None,
+        },
+        solution,
+    )
+}
+
+impl FeeMaximizer {
+    /// Verify a solution like [`verify`], from the current balance
+    /// (i.e. before solving, since solving executes the solution),
+    /// also checking the constraints, the block capacity and fees in the objective asset.
+    pub fn verify(&self, solution: &Solution) -> Result<Verification, FeeMaximizerError> {
+        self.constraints.validate(self.requests.len())?;
+        verify_problem(
+            &Problem {
+                balance: &self.balance,
+                requests: &self.requests,
+                constraints: &self.constraints,
+                capacity: self.capacity,
+                objective: &self.objective,
+                warm_start: None,
+            },
+            solution,
+        )
+    }
+}
+
+pub(crate) fn verify_problem(
+    problem: &Problem,
+    solution: &Solution,
+) -> Result<Verification, FeeMaximizerError> {
+    let requests = problem.requests;
+    let mut balance = problem.balance.clone();
+    let mut executed = requests
+        .iter()
+        .map(|r| vec![false; r.transactions.len()])
+        .collect::<Vec<_>>();
+    // positions of the first and last executed transactions of every request,
+    // since a solution may interleave the transactions of several requests.
+    let mut span = vec![None; requests.len()];
+    let mut usage = BlockUsage::default();
+    let mut total_fee = Amount::ZERO;
+
+    for (position, e) in solution.executed.iter().enumerate() {
+        let unknown = |reason: String| FeeMaximizerError::UnknownTransaction { position, reason };
+        let request = requests
+            .get(e.request)
+            .ok_or_else(|| unknown(format!("request {} does not exist", e.request)))?;
+        let tx = request.transactions.get(e.index).ok_or_else(|| {
+            unknown(format!(
+                "request {} has no transaction {}",
+                e.request, e.index
+            ))
+        })?;
+        if *tx != e.transaction {
+            return Err(unknown(format!(
+                "it differs from transaction {} of request {}",
+                e.index, e.request
+            )));
+        }
+        if std::mem::replace(&mut executed[e.request][e.index], true) {
+            return Err(unknown(format!(
+                "transaction {} of request {} is executed twice",
+                e.index, e.request
+            )));
+        }
+        let (first, _) = span[e.request].unwrap_or((position, position));
+        span[e.request] = Some((first, position));
+
+        if let Some((asset, shortfall)) = shortfall(&balance, tx) {
+            return Err(FeeMaximizerError::InsufficientBalance {
+                position,
+                address: tx.from.clone(),
+                asset: asset.clone(),
+                shortfall,
+            });
+        }
+        if !apply_transaction(&mut balance, tx) {
+            return Err(FeeMaximizerError::AmountOverflow);
+        }
+        usage = usage.with_transaction(tx.weight);
+        total_fee = total_fee
+            .checked_add(tx.fee_in(problem.objective))
+            .ok_or(FeeMaximizerError::AmountOverflow)?;
+    }
+
+    for (request, flags) in executed.iter().enumerate() {
+        if requests[request].atomic && flags.contains(&true) && flags.contains(&false) {
+            return Err(FeeMaximizerError::ConstraintViolated(format!(
+                "atomic request {request} is partially executed"
+            )));
+        }
+    }
+    for (request, depends_on) in problem.constraints.dependencies().iter().copied() {
+        if let Some((first, _)) = span[request] {
+            if span[depends_on].is_none_or(|(_, last)| last >= first) {
+                return Err(FeeMaximizerError::ConstraintViolated(format!(
+                    "request {request} is executed without its dependency {depends_on} before it"
+                )));
+            }
+        }
+    }
+    for (a, b) in problem.constraints.conflicts().iter().copied() {
+        if span[a].is_some() && span[b].is_some() {
+            return Err(FeeMaximizerError::ConstraintViolated(format!(
+                "conflicting requests {a} and {b} are both executed"
+            )));
+        }
+    }
+    if !problem.capacity.fits(usage) {
+        return Err(FeeMaximizerError::ConstraintViolated(format!(
+            "the block capacity is exceeded: {} transactions, weight {}",
+            usage.transactions, usage.weight
+        )));
+    }
+    if total_fee != solution.total_fee {
+        return Err(FeeMaximizerError::FeeMismatch {
+            claimed: solution.total_fee,
+            actual: total_fee,
+        });
+    }
+    Ok(Verification {
+        total_fee,
+        balance,
+        usage,
+    })
+}
+
+/// The asset and amount the sender of a transaction lacks to pay its amount and fee, if any.
+pub(crate) fn shortfall<'a>(balance: &Balance, tx: &'a Transaction) -> Option<(&'a Asset, Amount)> {
+    let available = |asset: &Asset| {
+        balance
+            .get(&(tx.from.clone(), asset.clone()))
+            .map_or(0, |b| b.minor() as i128)
+    };
+    let mut needed = vec![(&tx.asset, tx.amount.minor() as i128)];
+    if tx.fee_asset == tx.asset {
+        needed[0].1 += tx.fee.minor() as i128;
+    } else {
+        needed.push((&tx.fee_asset, tx.fee.minor() as i128));
+    }
+    needed.into_iter().find_map(|(asset, needed)| {
+        let missing = needed - available(asset);
+        (missing > 0).then(|| {
+            (
+                asset,
+                Amount::from_minor(missing.min(i64::MAX as i128) as i64),
+            )
+        })
+    })
+}
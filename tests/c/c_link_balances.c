@@ -0,0 +1,67 @@
+#include "tx_fee_maximizer.h"
+#include <stdio.h>
+#include <inttypes.h>
+#include "test_utils.h"
+
+int main(void) {
+    // initialize the fee maximizer.
+    FeeMaximizer *fm = fee_maximizer_init();
+    char *error = NULL;
+
+    // fund addresses without a csv, a null asset is the native asset.
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "A", NULL, 500000000, &error))
+    ASSERT_NO_ERR(fee_maximizer_add_balance(fm, "A", "", 500000000, &error))
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "A", "USD", 100, &error))
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "B", "USD", 100, &error))
+    if (fee_maximizer_remove_balance(fm, "B", "USD") != 100 ||
+        fee_maximizer_remove_balance(fm, "B", "USD") != -1) {
+        printf("Unexpected removed balance\n");
+        exit(1);
+    }
+
+    // send 5 from A to B with a fee of 1.
+    Request *req = request_init();
+    ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
+        "A",
+        "B",
+        500000000,
+        100000000,
+    }, &error))
+    fee_maximizer_add_request(fm, req);
+    request_destroy(req);
+
+    CSolution *sol = fee_maximizer_solve(fm, 1024, 32, 50, &error);
+    if (sol == NULL) {
+        printf("Error: %s\n", error);
+        exit(1);
+    }
+    solution_destroy(sol);
+
+    // read every final balance.
+    CBalances *balances = fee_maximizer_balances(fm);
+    const char *expected[][2] = {{"A", "Native"}, {"A", "USD"}, {"B", "Native"}, {"System", "Native"}};
+    const int64_t amounts[] = {400000000, 100, 500000000, 100000000};
+    if (balances->NBalances != 4) {
+        printf("Unexpected number of balances: %zu\n", balances->NBalances);
+        exit(1);
+    }
+    for (size_t i = 0; i < balances->NBalances; i++) {
+        CBalance b = balances->Balances[i];
+        printf("%s: %" PRId64 " %s\n", b.Address, b.Amount, b.Asset);
+        if (strcmp(b.Address, expected[i][0]) != 0 || strcmp(b.Asset, expected[i][1]) != 0 ||
+            b.Amount != amounts[i]) {
+            printf("Unexpected balance\n");
+            exit(1);
+        }
+    }
+    balances_destroy(balances);
+
+    // balances are validated like the csv.
+    int code = fee_maximizer_set_balance(fm, "C", NULL, -1, &error);
+    fee_maximizer_destroy(fm);
+    if (code != FEE_MAXIMIZER_ERROR_NEGATIVE_BALANCE) {
+        printf("Unexpected error code: %d\n", code);
+        exit(1);
+    }
+    ASSERT_ERR(code, "balance must be non-negative")
+}
@@ -0,0 +1,92 @@
+#include "tx_fee_maximizer.h"
+#include <stdio.h>
+#include <inttypes.h>
+#include "test_utils.h"
+
+int main(void) {
+    // initialize the fee maximizer.
+    FeeMaximizer *fm = fee_maximizer_init();
+    char *error = NULL;
+
+    // add balance and requests from in-memory csv.
+    const char *balance = "User,Balance\nA,10\nB,0\n";
+    ASSERT_NO_ERR(fee_maximizer_add_balance_from_csv_buffer(fm,
+                                                            (const uint8_t *) balance,
+                                                            strlen(balance),
+                                                            true,
+                                                            &error))
+
+    const char *requests = "request,from,to,amount,fee\n"
+                           "7,A,B,5,1\n"
+                           "8,A,B,5,2\n";
+    ASSERT_NO_ERR(fee_maximizer_add_requests_from_csv_buffer(fm,
+                                                             (const uint8_t *) requests,
+                                                             strlen(requests),
+                                                             true,
+                                                             &error))
+
+    // an empty buffer adds nothing.
+    ASSERT_NO_ERR(fee_maximizer_add_requests_from_csv_buffer(fm, NULL, 0, true, &error))
+
+    // solve for solution, checking its invariants even in release builds.
+    fee_maximizer_set_audit(fm, true);
+    CSolution *sol = fee_maximizer_solve(fm, 1024, 32, 50, &error);
+    if (sol == NULL) {
+        printf("Error: %s\n", error);
+        exit(1);
+    }
+    if (sol->NViolations != 0) {
+        printf("Unexpected violation: %s\n", sol->Violations[0]);
+        exit(1);
+    }
+    if (sol->NTxs != 1 || sol->Provenance[0].RequestId != 8 || sol->TotalFee != 200000000
+        || sol->UsedTransactions != 1 || sol->UsedWeight != 0) {
+        printf("Unexpected solution: %zu transactions, total fee = %" PRId64 "\n",
+               sol->NTxs,
+               sol->TotalFee);
+        exit(1);
+    }
+    printf("request %" PRIu64 " executed, total fee = %" PRId64 "\n",
+           sol->Provenance[0].RequestId,
+           sol->TotalFee);
+    solution_destroy(sol);
+
+    // a NUL byte in a buffer is escaped in the error message.
+    const char balance_nul[] = "User,Balance\nA,1\0" "2\n";
+    int nul_code = fee_maximizer_add_balance_from_csv_buffer(fm,
+                                                             (const uint8_t *) balance_nul,
+                                                             sizeof(balance_nul) - 1,
+                                                             true,
+                                                             &error);
+    if (nul_code != FEE_MAXIMIZER_ERROR_CSV
+        || strcmp(error, "csv error at line 2: invalid amount `1\\02`") != 0) {
+        printf("Unexpected error %d: %s\n", nul_code, error);
+        exit(1);
+    }
+    free(error);
+    const char requests_nul[] = "request,from,to,amount,fee\n9,A,B,1\0" "2,1\n";
+    nul_code = fee_maximizer_add_requests_from_csv_buffer(fm,
+                                                          (const uint8_t *) requests_nul,
+                                                          sizeof(requests_nul) - 1,
+                                                          true,
+                                                          &error);
+    if (nul_code != FEE_MAXIMIZER_ERROR_CSV || strstr(error, "`1\\02`") == NULL) {
+        printf("Unexpected error %d: %s\n", nul_code, error);
+        exit(1);
+    }
+    free(error);
+
+    // invalid csv is reported with its position.
+    const char *invalid = "User,Balance\nA,abc\n";
+    int code = fee_maximizer_add_balance_from_csv_buffer(fm,
+                                                         (const uint8_t *) invalid,
+                                                         strlen(invalid),
+                                                         true,
+                                                         &error);
+    fee_maximizer_destroy(fm);
+    if (code != FEE_MAXIMIZER_ERROR_CSV) {
+        printf("Unexpected error code: %d\n", code);
+        exit(1);
+    }
+    ASSERT_ERR(code, "csv error at line 2: invalid amount `abc`")
+}
@@ -0,0 +1,86 @@
+#include "tx_fee_maximizer.h"
+#include <stdio.h>
+#include <inttypes.h>
+#include <stdatomic.h>
+#include "test_utils.h"
+
+// a step curve, counting its calls from the solver threads.
+static double step_curve(double weight, void *user_data) {
+    atomic_size_t *calls = user_data;
+    atomic_fetch_add(calls, 1);
+    return weight < 0.5 ? 0.1 : 0.9;
+}
+
+static CSolution *solve(FeeMaximizer *fm, SolverConfig *config) {
+    char *error = NULL;
+    CSolution *sol = fee_maximizer_solve_with(fm, "genetic", config, &error);
+    if (sol == NULL) {
+        printf("Error: %s\n", error);
+        exit(1);
+    }
+    return sol;
+}
+
+int main(void) {
+    // initialize the fee maximizer.
+    FeeMaximizer *fm = fee_maximizer_init();
+    char *error = NULL;
+
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "A", NULL, 1000000000, &error))
+    Request *req = request_init();
+    ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
+        "A",
+        "B",
+        100000000,
+        200000000,
+    }, &error))
+    fee_maximizer_add_request(fm, req);
+    request_destroy(req);
+
+    SolverConfig *config = solver_config_init();
+    solver_config_set_genetic_params(config, 64, 4, 10);
+    solver_config_set_seed(config, 1);
+
+    // unknown curves are rejected when set.
+    if (solver_config_set_selection_curve(config, "cubic", &error) !=
+        FEE_MAXIMIZER_ERROR_INVALID_SOLVER_PARAMS) {
+        printf("Error expected for an unknown curve\n");
+        exit(1);
+    }
+    printf("Error: %s\n", error);
+    free(error);
+
+    ASSERT_NO_ERR(solver_config_set_selection_curve(config, "sigmoid:4", &error))
+    solver_config_set_mutation_rate(config, 0.05);
+    solver_config_set_exploration(config, 0.1, 0.1);
+    CSolution *sol = solve(fm, config);
+    if (sol->TotalFee != 200000000) {
+        printf("Unexpected total fee with the sigmoid curve: %" PRId64 "\n", sol->TotalFee);
+        exit(1);
+    }
+    solution_destroy(sol);
+
+    atomic_size_t calls = 0;
+    solver_config_set_custom_selection_curve(config, step_curve, &calls);
+    sol = solve(fm, config);
+    if (sol->TotalFee != 200000000 || atomic_load(&calls) == 0) {
+        printf("Unexpected result with the custom curve: total fee = %" PRId64 ", %zu calls\n",
+               sol->TotalFee,
+               atomic_load(&calls));
+        exit(1);
+    }
+    solution_destroy(sol);
+
+    // invalid rates are rejected when solving.
+    solver_config_set_mutation_rate(config, 0.8);
+    if (fee_maximizer_solve_with(fm, "genetic", config, &error) != NULL ||
+        fee_maximizer_last_error_code() != FEE_MAXIMIZER_ERROR_INVALID_SOLVER_PARAMS) {
+        printf("Error expected for a mutation rate above 0.5\n");
+        exit(1);
+    }
+    printf("Error: %s\n", error);
+    free(error);
+
+    solver_config_destroy(config);
+    fee_maximizer_destroy(fm);
+}
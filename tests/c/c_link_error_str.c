@@ -13,10 +13,11 @@ int main(int argc, char *argv[]) {
     char *error = NULL;
 
     // add balance from csv.
-    ASSERT_ERR(fee_maximizer_add_balance_from_csv(fm,
-                                                  argv[1],
-                                                  true,
-                                                  &error),
-               "No such file or directory (os error 2)")
+    int code = fee_maximizer_add_balance_from_csv(fm, argv[1], true, &error);
+    if (code != FEE_MAXIMIZER_ERROR_IO || fee_maximizer_last_error_code() != code) {
+        printf("Unexpected error code: %d\n", code);
+        exit(1);
+    }
+    ASSERT_ERR(code, "No such file or directory (os error 2)")
 
 }
@@ -0,0 +1,64 @@
+#include "tx_fee_maximizer.h"
+#include <stdio.h>
+#include <inttypes.h>
+#include "test_utils.h"
+
+typedef struct Observer {
+    size_t generations;
+    CancellationToken *token;
+} Observer;
+
+// count the generations, and cancel the search after the third one.
+static void on_progress(const CProgress *progress, void *user_data) {
+    Observer *observer = user_data;
+    printf("generation %zu: best fee = %" PRId64 ", mean fee = %" PRId64 ", feasible = %.2f\n",
+           progress->Generation,
+           progress->BestFee,
+           progress->MeanFee,
+           progress->FeasibleFraction);
+    observer->generations++;
+    if (progress->Generation == 2) {
+        cancellation_token_cancel(observer->token);
+    }
+}
+
+int main(void) {
+    // initialize the fee maximizer.
+    FeeMaximizer *fm = fee_maximizer_init();
+    char *error = NULL;
+
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "A", NULL, 1000000000, &error))
+    Request *req = request_init();
+    ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
+        "A",
+        "B",
+        100000000,
+        200000000,
+    }, &error))
+    fee_maximizer_add_request(fm, req);
+    request_destroy(req);
+
+    Observer observer = {0, cancellation_token_init()};
+    SolverConfig *config = solver_config_init();
+    solver_config_set_genetic_params(config, 64, 4, 1000);
+    solver_config_set_progress_callback(config, on_progress, &observer);
+    solver_config_set_cancellation_token(config, observer.token);
+
+    CSolution *sol = fee_maximizer_solve_with(fm, "genetic", config, &error);
+    solver_config_destroy(config);
+    cancellation_token_destroy(observer.token);
+    if (sol == NULL) {
+        printf("Error: %s\n", error);
+        exit(1);
+    }
+    if (observer.generations != 3 || sol->TotalFee != 200000000
+        || fee_maximizer_last_stop_reason(fm) != FEE_MAXIMIZER_STOP_CANCELLED
+        || fee_maximizer_last_generations(fm) != 3) {
+        printf("Unexpected result: %zu generations, total fee = %" PRId64 "\n",
+               observer.generations,
+               sol->TotalFee);
+        exit(1);
+    }
+    solution_destroy(sol);
+    fee_maximizer_destroy(fm);
+}
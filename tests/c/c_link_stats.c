@@ -0,0 +1,67 @@
+#include "tx_fee_maximizer.h"
+#include <stdio.h>
+#include <inttypes.h>
+#include "test_utils.h"
+
+static CSolution *solve(FeeMaximizer *fm, const char *solver_name, SolverConfig *config) {
+    char *error = NULL;
+    CSolution *sol = fee_maximizer_solve_with(fm, solver_name, config, &error);
+    if (sol == NULL) {
+        printf("Error: %s\n", error);
+        exit(1);
+    }
+    return sol;
+}
+
+int main(void) {
+    // initialize the fee maximizer.
+    FeeMaximizer *fm = fee_maximizer_init();
+    char *error = NULL;
+
+    ASSERT_NO_ERR(fee_maximizer_set_balance(fm, "A", NULL, 1000000000, &error))
+    Request *req = request_init();
+    ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
+        "A",
+        "B",
+        100000000,
+        200000000,
+    }, &error))
+    fee_maximizer_add_request(fm, req);
+    request_destroy(req);
+
+    if (fee_maximizer_last_stop_reason(fm) != FEE_MAXIMIZER_STOP_NONE) {
+        printf("Unexpected stop reason before solving\n");
+        exit(1);
+    }
+
+    // the best fee cannot improve after the first generation.
+    SolverConfig *config = solver_config_init();
+    solver_config_set_genetic_params(config, 64, 4, 1000);
+    solver_config_set_seed(config, 1);
+    solver_config_set_patience(config, 3);
+    CSolution *sol = solve(fm, "genetic", config);
+    size_t generations = fee_maximizer_last_generations(fm);
+    printf("genetic: stop reason = %d, generations = %zu, best fee = %" PRId64 "\n",
+           fee_maximizer_last_stop_reason(fm),
+           generations,
+           fee_maximizer_last_best_fee(fm));
+    if (fee_maximizer_last_stop_reason(fm) != FEE_MAXIMIZER_STOP_CONVERGED
+        || generations == 0 || generations >= 1000
+        || fee_maximizer_last_best_fee(fm) != sol->TotalFee
+        || sol->TotalFee != 200000000) {
+        printf("Unexpected statistics of the genetic solver\n");
+        exit(1);
+    }
+    solution_destroy(sol);
+
+    sol = solve(fm, "greedy", config);
+    solver_config_destroy(config);
+    if (fee_maximizer_last_stop_reason(fm) != FEE_MAXIMIZER_STOP_COMPLETED
+        || fee_maximizer_last_generations(fm) != 0
+        || fee_maximizer_last_best_fee(fm) != sol->TotalFee) {
+        printf("Unexpected statistics of the greedy solver\n");
+        exit(1);
+    }
+    solution_destroy(sol);
+    fee_maximizer_destroy(fm);
+}
@@ -1,5 +1,6 @@
 #include "tx_fee_maximizer.h"
 #include <stdio.h>
+#include <inttypes.h>
 #include "test_utils.h"
 
 int main(int argc, char *argv[]) {
@@ -25,17 +26,20 @@ int main(int argc, char *argv[]) {
     ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
         "A",
         "B",
-        1.0,
-        2.0,
+        100000000,
+        200000000,
     }, &error))
 
     ASSERT_NO_ERR(request_add_transaction(req, (CTransaction) {
             "B",
             "A",
-            1.0,
-            2.0,
+            100000000,
+            200000000,
     }, &error))
 
+    // the id is reported in the solution.
+    request_set_id(req, 42);
+
     // add request to fee maximizer.
     fee_maximizer_add_request(fm, req);
 
@@ -50,17 +54,22 @@ int main(int argc, char *argv[]) {
     }
 
     for (size_t i = 0; i < sol->NTxs; i++) {
-        printf("%s -> %s: amount = %f, fee = %f\n",
+        printf("request %" PRIu64 " tx %zu: %s -> %s: amount = %" PRId64 ", fee = %" PRId64 "\n",
+               sol->Provenance[i].RequestId,
+               sol->Provenance[i].Index,
                sol->Txs[i].From,
                sol->Txs[i].To,
                sol->Txs[i].Amount,
                sol->Txs[i].Fee);
     }
+    printf("%zu rejected transactions, total fee = %" PRId64 "\n",
+           sol->NRejected,
+           sol->TotalFee);
 
     // query balance.
-    printf("System balance = %f\n", fee_maximizer_query_address_balance(fm, "System"));
-    printf("A's balance = %f\n", fee_maximizer_query_address_balance(fm, "A"));
-    printf("B's balance = %f\n", fee_maximizer_query_address_balance(fm, "B"));
+    printf("System balance = %" PRId64 "\n", fee_maximizer_query_address_balance(fm, "System"));
+    printf("A's balance = %" PRId64 "\n", fee_maximizer_query_address_balance(fm, "A"));
+    printf("B's balance = %" PRId64 "\n", fee_maximizer_query_address_balance(fm, "B"));
 
     // destroy the solution
     solution_destroy(sol);
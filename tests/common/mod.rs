@@ -0,0 +1,40 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+use tx_fee_maximizer::*;
+
+pub fn address(s: &str) -> Address {
+    Address::from_string(s.to_string()).unwrap()
+}
+
+pub fn asset(s: &str) -> Asset {
+    Asset::from_string(s.to_string()).unwrap()
+}
+
+/// A request of (from, to, amount, fee) transactions in the native asset, in units.
+pub fn request(txs: &[(&str, &str, i64, i64)]) -> Request {
+    let mut req = Request::init_empty();
+    for (from, to, amount, fee) in txs.iter().copied() {
+        req.add_transaction(Transaction::new(
+            address(from),
+            address(to),
+            Amount::from_units(amount),
+            Amount::from_units(fee),
+        ))
+        .unwrap();
+    }
+    req
+}
+
+/// A fee maximizer with these balances in the native asset, in units.
+pub fn fee_maximizer(balances: &[(&str, i64)]) -> FeeMaximizer {
+    let mut fm = FeeMaximizer::init_empty();
+    for (account, balance) in balances {
+        fm.set_balance(
+            address(account),
+            NATIVE_ASSET.clone(),
+            Amount::from_units(*balance),
+        )
+        .unwrap();
+    }
+    fm
+}
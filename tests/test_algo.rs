@@ -1,20 +1,19 @@
+// the original tests build addresses with `format!`.
+#![allow(clippy::useless_format)]
+
 use tx_fee_maximizer::*;
 
+/// genetic solver with the default parameters and a fixed seed,
+/// so that the results are reproducible.
+fn seeded_solver() -> GeneticSolver {
+    GeneticSolver::new(SolverConfig {
+        seed: Some(20230607),
+        ..SolverConfig::default()
+    })
+}
+
 mod test_cases {
     use super::*;
-    use csv::Reader;
-    use serde::Deserialize;
-    use std::collections::HashMap;
-    use std::path::Path;
-
-    #[derive(Deserialize, Clone)]
-    struct TxEntry {
-        request: usize,
-        from: String,
-        to: String,
-        amount: f64,
-        fee: f64,
-    }
 
     #[test]
     fn test_tx_dependency_01() {
@@ -22,11 +21,12 @@ mod test_cases {
         fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
             .unwrap();
 
-        write_requests(&mut fm, "./test_data/cases/tx_dependency_01.csv");
+        fm.add_requests_from_csv("./test_data/cases/tx_dependency_01.csv", true)
+            .unwrap();
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
         assert_eq!(tx.len(), 4);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 21.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(21));
     }
 
     #[test]
@@ -35,11 +35,12 @@ mod test_cases {
         fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
             .unwrap();
 
-        write_requests(&mut fm, "./test_data/cases/tx_dependency_02.csv");
+        fm.add_requests_from_csv("./test_data/cases/tx_dependency_02.csv", true)
+            .unwrap();
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
         assert_eq!(tx.len(), 8);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 21.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(21));
     }
 
     #[test]
@@ -48,11 +49,12 @@ mod test_cases {
         fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
             .unwrap();
 
-        write_requests(&mut fm, "./test_data/cases/tx_competition_01.csv");
+        fm.add_requests_from_csv("./test_data/cases/tx_competition_01.csv", true)
+            .unwrap();
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
         assert_eq!(tx.len(), 2);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 60.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(60));
     }
 
     #[test]
@@ -61,11 +63,12 @@ mod test_cases {
         fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
             .unwrap();
 
-        write_requests(&mut fm, "./test_data/cases/tx_competition_02.csv");
+        fm.add_requests_from_csv("./test_data/cases/tx_competition_02.csv", true)
+            .unwrap();
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
         assert_eq!(tx.len(), 3);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 90.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(90));
     }
 
     #[test]
@@ -74,41 +77,55 @@ mod test_cases {
         fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
             .unwrap();
 
-        write_requests(&mut fm, "./test_data/cases/long_chain_01.csv");
+        fm.add_requests_from_csv("./test_data/cases/long_chain_01.csv", true)
+            .unwrap();
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
         assert_eq!(tx.len(), 5);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 70.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(70));
     }
 
-    fn load_test_case<P: AsRef<Path>>(csv_path: P) -> HashMap<usize, Vec<TxEntry>> {
-        let mut reader = Reader::from_path(csv_path).unwrap();
-        let mut requests = HashMap::new();
-        for entry in reader.deserialize() {
-            let entry: TxEntry = entry.unwrap();
-            requests
-                .entry(entry.request)
-                .and_modify(|o: &mut Vec<TxEntry>| o.push(entry.clone()))
-                .or_insert(vec![entry]);
-        }
-        requests
-    }
+    /// the exact solver reaches the same fees as the genetic solver on every case.
+    #[test]
+    fn test_exact_cases() {
+        for (case, num_txs, fee) in [
+            ("tx_dependency_01", 4, 21),
+            ("tx_dependency_02", 8, 21),
+            ("tx_competition_01", 2, 60),
+            ("tx_competition_02", 3, 90),
+            ("long_chain_01", 5, 70),
+            ("atomic_01", 3, 45),
+        ] {
+            let mut fm = FeeMaximizer::init_empty();
+            fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+                .unwrap();
 
-    fn write_requests<P: AsRef<Path>>(fm: &mut FeeMaximizer, csv_path: P) {
-        for request in load_test_case(csv_path).into_values() {
-            let mut req = Request::init_empty();
-            for e in request {
-                req.add_transaction(Transaction {
-                    from: Address::from_string(e.from).unwrap(),
-                    to: Address::from_string(e.to).unwrap(),
-                    amount: e.amount,
-                    fee: e.fee,
-                })
+            fm.add_requests_from_csv(format!("./test_data/cases/{case}.csv"), true)
                 .unwrap();
-            }
-            fm.add_request(&req);
+
+            let tx = fm.solve_with(&ExactSolver).unwrap();
+            assert_eq!(tx.len(), num_txs, "{case}");
+            assert_eq!(
+                fm.get_balance(&SYSTEM_ADDRESS),
+                Amount::from_units(fee),
+                "{case}"
+            );
         }
     }
+
+    #[test]
+    fn test_atomic_01() {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+            .unwrap();
+
+        fm.add_requests_from_csv("./test_data/cases/atomic_01.csv", true)
+            .unwrap();
+
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
+        assert_eq!(tx.len(), 3);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(45));
+    }
 }
 
 mod trivial_cases {
@@ -125,57 +142,57 @@ mod trivial_cases {
         let address_c: Address = Address::from_string(format!("C")).unwrap();
         let address_d: Address = Address::from_string(format!("D")).unwrap();
 
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 20.0);
-        assert_eq!(fm.get_balance(&address_a), 100.0);
-        assert_eq!(fm.get_balance(&address_b), 100.0);
-        assert_eq!(fm.get_balance(&address_c), 100.0);
-        assert_eq!(fm.get_balance(&address_d), 100.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(20));
+        assert_eq!(fm.get_balance(&address_a), Amount::from_units(100));
+        assert_eq!(fm.get_balance(&address_b), Amount::from_units(100));
+        assert_eq!(fm.get_balance(&address_c), Amount::from_units(100));
+        assert_eq!(fm.get_balance(&address_d), Amount::from_units(100));
 
         // add 50 requests
         for _ in 0..50 {
             let mut req = Request::init_empty();
-            req.add_transaction(Transaction {
-                from: address_a.clone(),
-                to: address_b.clone(),
-                amount: 1.0,
-                fee: 1.0,
-            })
+            req.add_transaction(Transaction::new(
+                address_a.clone(),
+                address_b.clone(),
+                Amount::from_units(1),
+                Amount::from_units(1),
+            ))
             .unwrap();
 
-            req.add_transaction(Transaction {
-                from: address_b.clone(),
-                to: address_c.clone(),
-                amount: 1.0,
-                fee: 1.0,
-            })
+            req.add_transaction(Transaction::new(
+                address_b.clone(),
+                address_c.clone(),
+                Amount::from_units(1),
+                Amount::from_units(1),
+            ))
             .unwrap();
 
-            req.add_transaction(Transaction {
-                from: address_c.clone(),
-                to: address_d.clone(),
-                amount: 1.0,
-                fee: 1.0,
-            })
+            req.add_transaction(Transaction::new(
+                address_c.clone(),
+                address_d.clone(),
+                Amount::from_units(1),
+                Amount::from_units(1),
+            ))
             .unwrap();
 
-            req.add_transaction(Transaction {
-                from: address_d.clone(),
-                to: address_a.clone(),
-                amount: 1.0,
-                fee: 1.0,
-            })
+            req.add_transaction(Transaction::new(
+                address_d.clone(),
+                address_a.clone(),
+                Amount::from_units(1),
+                Amount::from_units(1),
+            ))
             .unwrap();
 
             fm.add_request(&req);
         }
 
-        fm.solve(8192, 32, 50).unwrap();
+        fm.solve_with(&seeded_solver()).unwrap();
 
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 220.0);
-        assert_eq!(fm.get_balance(&address_a), 50.0);
-        assert_eq!(fm.get_balance(&address_b), 50.0);
-        assert_eq!(fm.get_balance(&address_c), 50.0);
-        assert_eq!(fm.get_balance(&address_d), 50.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(220));
+        assert_eq!(fm.get_balance(&address_a), Amount::from_units(50));
+        assert_eq!(fm.get_balance(&address_b), Amount::from_units(50));
+        assert_eq!(fm.get_balance(&address_c), Amount::from_units(50));
+        assert_eq!(fm.get_balance(&address_d), Amount::from_units(50));
     }
 
     #[test]
@@ -190,43 +207,43 @@ mod trivial_cases {
         let address_d: Address = Address::from_string(format!("D")).unwrap();
 
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: address_a.clone(),
-            to: address_b.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_a.clone(),
+            address_b.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
 
-        req.add_transaction(Transaction {
-            from: address_b.clone(),
-            to: address_c.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_b.clone(),
+            address_c.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
 
-        req.add_transaction(Transaction {
-            from: address_c.clone(),
-            to: address_d.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_c.clone(),
+            address_d.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
 
-        req.add_transaction(Transaction {
-            from: address_d.clone(),
-            to: address_a.clone(),
-            amount: 1.0,
-            fee: 1.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_d.clone(),
+            address_a.clone(),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
         .unwrap();
         fm.add_request(&req);
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
 
         assert_eq!(tx.len(), 4);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 21.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(21));
     }
 
     #[test]
@@ -241,48 +258,113 @@ mod trivial_cases {
         let address_d: Address = Address::from_string(format!("D")).unwrap();
 
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: address_a.clone(),
-            to: address_b.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_a.clone(),
+            address_b.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
         fm.add_request(&req);
 
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: address_b.clone(),
-            to: address_c.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_b.clone(),
+            address_c.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
         fm.add_request(&req);
 
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: address_c.clone(),
-            to: address_d.clone(),
-            amount: 2.0,
-            fee: 0.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_c.clone(),
+            address_d.clone(),
+            Amount::from_units(2),
+            Amount::from_units(0),
+        ))
         .unwrap();
         fm.add_request(&req);
 
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: address_d.clone(),
-            to: address_a.clone(),
-            amount: 1.0,
-            fee: 1.0,
-        })
+        req.add_transaction(Transaction::new(
+            address_d.clone(),
+            address_a.clone(),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
         .unwrap();
         fm.add_request(&req);
 
-        let tx = fm.solve(8192, 32, 50).unwrap();
+        let tx = fm.solve_with(&seeded_solver()).unwrap();
 
         assert_eq!(tx.len(), 4);
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 21.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(21));
+    }
+
+    /// 0.1 + 0.2 must spend a balance of exactly 0.3.
+    #[test]
+    fn test_decimal_amounts() {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_balance_from_csv("test_data/decimal_balance.csv", true)
+            .unwrap();
+
+        let address_a: Address = Address::from_string(format!("A")).unwrap();
+        let address_b: Address = Address::from_string(format!("B")).unwrap();
+
+        let mut req = Request::init_empty();
+        req.add_transaction(Transaction::new(
+            address_a.clone(),
+            address_b.clone(),
+            "0.1".parse().unwrap(),
+            "0.2".parse().unwrap(),
+        ))
+        .unwrap();
+        fm.add_request(&req);
+
+        let tx = fm.solve_with(&ExactSolver).unwrap();
+
+        assert_eq!(tx.len(), 1);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), "0.2".parse().unwrap());
+        assert_eq!(fm.get_balance(&address_a), Amount::ZERO);
+        assert_eq!(fm.get_balance(&address_b), "0.1".parse().unwrap());
+    }
+}
+
+mod csv_requests {
+    use super::*;
+
+    /// requests keep the order of their first row, and transactions the order of rows.
+    #[test]
+    fn test_request_order() {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_requests_from_csv("test_data/cases/interleaved_01.csv", true)
+            .unwrap();
+        let requests = fm.requests();
+        assert_eq!(requests.len(), 2);
+
+        assert_eq!(requests[0].id(), Some(7));
+        assert!(!requests[0].is_atomic());
+        let txs = requests[0].transactions();
+        assert_eq!(txs[0].to, Address::from_string("B".to_string()).unwrap());
+        assert_eq!(txs[0].weight, 3);
+        assert_eq!(txs[1].to, Address::from_string("A".to_string()).unwrap());
+        assert_eq!(txs[1].weight, 2);
+
+        assert_eq!(requests[1].id(), Some(3));
+        assert!(requests[1].is_atomic());
+        assert_eq!(requests[1].transactions().len(), 2);
+    }
+
+    /// nothing is added if a row is invalid.
+    #[test]
+    fn test_invalid_row() {
+        let mut fm = FeeMaximizer::init_empty();
+        let err = fm
+            .add_requests_from_csv("test_data/cases/system_address_illegal.csv", true)
+            .unwrap_err();
+        assert!(matches!(err, FeeMaximizerError::SystemAddress), "{err}");
+        assert!(fm.requests().is_empty());
     }
 }
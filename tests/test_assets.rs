@@ -0,0 +1,191 @@
+mod common;
+
+use common::{address, asset};
+use tx_fee_maximizer::*;
+
+/// a transfer of `amount` USD from A to `to`, paying `fee` in `fee_asset`.
+fn usd_request(to: &str, amount: i64, fee: i64, fee_asset: &str) -> Request {
+    let mut req = Request::init_empty();
+    req.add_transaction(
+        Transaction::new(
+            address("A"),
+            address(to),
+            Amount::from_units(amount),
+            Amount::from_units(fee),
+        )
+        .with_asset(asset("USD"))
+        .with_fee_asset(asset(fee_asset)),
+    )
+    .unwrap();
+    req
+}
+
+fn init() -> FeeMaximizer {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/multi_asset_balance.csv", true)
+        .unwrap();
+    fm
+}
+
+fn solve_all(fm: &FeeMaximizer) -> Vec<FeeMaximizer> {
+    let config = SolverConfig {
+        population_size: 256,
+        selection_size: 8,
+        num_generation: 10,
+        seed: Some(1),
+        ..SolverConfig::default()
+    };
+    SOLVER_NAMES
+        .iter()
+        .map(|name| {
+            let mut fm = fm.clone();
+            let solver = solver_from_name(name, config.clone()).unwrap();
+            fm.solve_with(solver.as_ref()).unwrap();
+            fm
+        })
+        .collect()
+}
+
+#[test]
+fn test_balance_csv_with_asset() {
+    let fm = init();
+    assert_eq!(
+        fm.get_asset_balance(&address("A"), &asset("USD")),
+        Amount::from_units(100)
+    );
+    assert_eq!(fm.get_balance(&address("A")), Amount::from_units(10));
+    assert_eq!(
+        fm.get_asset_balance(&address("B"), &asset("USD")),
+        Amount::from_minor(-1)
+    );
+}
+
+/// without header, columns are read by position as User, Balance and an optional Asset.
+#[test]
+fn test_balance_csv_without_header() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_reader(&b"A,100\nB,5,USD\nC,1.5\n"[..], false)
+        .unwrap();
+    assert_eq!(fm.get_balance(&address("A")), Amount::from_units(100));
+    assert_eq!(
+        fm.get_asset_balance(&address("B"), &asset("USD")),
+        Amount::from_units(5)
+    );
+    assert_eq!(fm.get_balance(&address("C")), "1.5".parse().unwrap());
+}
+
+/// amounts and fees are paid from different balances.
+#[test]
+fn test_fee_in_native_asset() {
+    let mut fm = init();
+    fm.add_request(&usd_request("B", 50, 3, NATIVE_ASSET_STR));
+    fm.add_request(&usd_request("B", 60, 5, NATIVE_ASSET_STR));
+    for fm in solve_all(&fm) {
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(5));
+        assert_eq!(fm.get_balance(&address("A")), Amount::from_units(5));
+        assert_eq!(
+            fm.get_asset_balance(&address("A"), &asset("USD")),
+            Amount::from_units(40)
+        );
+        assert_eq!(
+            fm.get_asset_balance(&address("B"), &asset("USD")),
+            Amount::from_units(60)
+        );
+    }
+}
+
+/// every solver returns the same balances for the same selection.
+#[test]
+fn test_same_balance() {
+    let mut fm = init();
+    fm.add_request(&usd_request("B", 50, 3, NATIVE_ASSET_STR));
+    fm.add_request(&usd_request("C", 60, 5, "USD"));
+    let solved = solve_all(&fm);
+    for fm in &solved[1..] {
+        assert_eq!(fm.balance(), solved[0].balance());
+    }
+    assert_eq!(
+        solved[0].get_asset_balance(&address("C"), &asset("USD")),
+        Amount::from_minor(-1)
+    );
+}
+
+/// the fee asset balance must be sufficient.
+#[test]
+fn test_insufficient_fee_asset() {
+    let mut fm = init();
+    fm.add_request(&usd_request("B", 10, 11, NATIVE_ASSET_STR));
+    for fm in solve_all(&fm) {
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::ZERO);
+        assert_eq!(
+            fm.get_asset_balance(&address("A"), &asset("USD")),
+            Amount::from_units(100)
+        );
+    }
+}
+
+/// only fees in the objective asset are maximized.
+#[test]
+fn test_objective_asset() {
+    let mut fm = init();
+    let native = fm.add_request(&usd_request("B", 10, 4, NATIVE_ASSET_STR));
+    let usd = fm.add_request(&usd_request("C", 10, 3, "USD"));
+    fm.add_conflict(native, usd);
+    for fm in solve_all(&fm) {
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(4));
+    }
+
+    fm.set_objective_asset(asset("USD"));
+    for fm in solve_all(&fm) {
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::ZERO);
+        assert_eq!(
+            fm.get_asset_balance(&SYSTEM_ADDRESS, &asset("USD")),
+            Amount::from_units(3)
+        );
+    }
+}
+
+#[test]
+fn test_balance_api() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.set_balance(address("A"), asset("USD"), Amount::from_units(10))
+        .unwrap();
+    fm.add_balance(address("A"), asset("USD"), Amount::from_units(5))
+        .unwrap();
+    fm.add_balance(address("B"), NATIVE_ASSET.clone(), Amount::from_units(1))
+        .unwrap();
+    assert_eq!(
+        fm.get_asset_balance(&address("A"), &asset("USD")),
+        Amount::from_units(15)
+    );
+    assert_eq!(fm.get_balance(&address("B")), Amount::from_units(1));
+
+    // set replaces the balance.
+    fm.set_balance(address("A"), asset("USD"), Amount::from_units(2))
+        .unwrap();
+    assert_eq!(
+        fm.get_asset_balance(&address("A"), &asset("USD")),
+        Amount::from_units(2)
+    );
+
+    // same validation as the csv.
+    assert!(matches!(
+        fm.set_balance(address("A"), asset("USD"), Amount::from_minor(-1)),
+        Err(FeeMaximizerError::NegativeBalance)
+    ));
+    assert!(matches!(
+        fm.add_balance(address("A"), asset("USD"), Amount::MAX),
+        Err(FeeMaximizerError::AmountOverflow)
+    ));
+    assert_eq!(
+        fm.get_asset_balance(&address("A"), &asset("USD")),
+        Amount::from_units(2)
+    );
+
+    assert_eq!(
+        fm.remove_balance(&address("B"), &NATIVE_ASSET),
+        Some(Amount::from_units(1))
+    );
+    assert_eq!(fm.remove_balance(&address("B"), &NATIVE_ASSET), None);
+    assert_eq!(fm.balance().len(), 2);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{address, request};
+use tx_fee_maximizer::*;
+
+fn key(s: &str) -> (Address, Asset) {
+    (address(s), NATIVE_ASSET.clone())
+}
+
+/// A greedy solver whose balance is tampered with after solving.
+struct TamperingSolver(fn(&mut Balance));
+
+impl Solver for TamperingSolver {
+    fn name(&self) -> &str {
+        "tampering"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        let mut output = GreedySolver.solve(problem)?;
+        (self.0)(&mut output.balance);
+        Ok(output)
+    }
+}
+
+/// A sends 5 to B with a fee of 1, and B sends 2 to A with a fee of 1.
+fn fee_maximizer() -> FeeMaximizer {
+    let mut fm = common::fee_maximizer(&[("A", 10)]);
+    for (from, to, amount) in [("A", "B", 5), ("B", "A", 2)] {
+        fm.add_request(&request(&[(from, to, amount, 1)]));
+    }
+    fm.set_audit(true);
+    fm
+}
+
+#[test]
+fn test_audit_sound() {
+    let mut fm = fee_maximizer();
+    let solution = fm.solve_with(&GreedySolver).unwrap();
+    assert_eq!(solution.total_fee, Amount::from_units(2));
+    assert!(solution.violations.is_empty());
+    assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(2));
+}
+
+#[test]
+fn test_supply_changed() {
+    let mut fm = fee_maximizer();
+    let initial = fm.balance().clone();
+    let solution = fm
+        .solve_with(&TamperingSolver(|balance| {
+            *balance.get_mut(&key("B")).unwrap() += Amount::from_minor(1);
+        }))
+        .unwrap();
+    assert_eq!(
+        solution.violations,
+        vec![Violation::SupplyChanged {
+            asset: NATIVE_ASSET.clone(),
+            change: Amount::from_minor(1),
+        }]
+    );
+    // the tampered balance is not committed.
+    assert_eq!(fm.balance(), &initial);
+}
+
+#[test]
+fn test_negative_balance() {
+    let mut fm = fee_maximizer();
+    let solution = fm
+        .solve_with(&TamperingSolver(|balance| {
+            *balance.get_mut(&key("A")).unwrap() -= Amount::from_units(10);
+            *balance.get_mut(&key("B")).unwrap() += Amount::from_units(10);
+        }))
+        .unwrap();
+    assert_eq!(
+        solution.violations,
+        vec![Violation::NegativeBalance {
+            address: address("A"),
+            asset: NATIVE_ASSET.clone(),
+            amount: Amount::from_units(-4),
+        }]
+    );
+}
+
+/// the system gains less than the fees, the difference going to A.
+#[test]
+fn test_fee_mismatch() {
+    let mut fm = fee_maximizer();
+    let solution = fm
+        .solve_with(&TamperingSolver(|balance| {
+            *balance.get_mut(&key(SYSTEM_ADDRESS_STR)).unwrap() -= Amount::from_units(1);
+            *balance.get_mut(&key("A")).unwrap() += Amount::from_units(1);
+        }))
+        .unwrap();
+    assert_eq!(
+        solution.violations,
+        vec![Violation::FeeMismatch {
+            asset: NATIVE_ASSET.clone(),
+            system_gain: Amount::from_units(1),
+            fees: Amount::from_units(2),
+        }]
+    );
+    assert_eq!(
+        solution.violations[0].to_string(),
+        "system gained 1 Native but the fees in Native sum to 2"
+    );
+    // violations are part of the JSON solution.
+    let mut json = Vec::new();
+    solution.write_json(&mut json).unwrap();
+    assert_eq!(Solution::read_json(json.as_slice()).unwrap(), solution);
+}
+
+/// without auditing, the tampered balance is committed.
+#[test]
+fn test_audit_off() {
+    let mut fm = fee_maximizer();
+    fm.set_audit(false);
+    let solution = fm
+        .solve_with(&TamperingSolver(|balance| {
+            *balance.get_mut(&key("B")).unwrap() += Amount::from_minor(1);
+        }))
+        .unwrap();
+    assert!(solution.violations.is_empty());
+    assert_eq!(
+        fm.get_balance(&address("B")),
+        Amount::from_units(2) + Amount::from_minor(1)
+    );
+}
@@ -1,4 +1,10 @@
-use tx_fee_maximizer::{Address, FeeMaximizer, Request, Transaction, SYSTEM_ADDRESS};
+// the original tests build addresses with `format!`.
+#![allow(clippy::useless_format)]
+
+use tx_fee_maximizer::{
+    Address, Amount, ExactSolver, FeeMaximizer, FeeMaximizerError, Request, Transaction,
+    SYSTEM_ADDRESS,
+};
 
 mod illegal_inputs {
     use super::*;
@@ -16,54 +22,98 @@ mod illegal_inputs {
         match fm.add_balance_from_csv("test_data/negative_balance_illegal.csv", true) {
             Ok(_) => panic!("negative balance should not be allowed"),
             Err(e) => {
-                assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+                assert!(matches!(e, FeeMaximizerError::NegativeBalance));
                 assert_eq!(e.to_string(), "balance must be non-negative".to_string());
             }
         }
     }
 
+    /// csv errors report their position
+    #[test]
+    fn test_malformed_balance() {
+        let mut fm = FeeMaximizer::init_empty();
+        let err = fm
+            .add_balance_from_csv("test_data/malformed_balance_illegal.csv", true)
+            .unwrap_err();
+        assert!(
+            matches!(&err, FeeMaximizerError::Csv { line: Some(3), .. }),
+            "{err:?}"
+        );
+        assert_eq!(err.to_string(), "csv error at line 3: invalid amount `abc`");
+    }
+
+    /// missing files are io errors
+    #[test]
+    fn test_missing_balance_file() {
+        let mut fm = FeeMaximizer::init_empty();
+        let err = fm
+            .add_balance_from_csv("test_data/does_not_exist.csv", true)
+            .unwrap_err();
+        assert!(matches!(err, FeeMaximizerError::Io(_)), "{err:?}");
+    }
+
+    /// in-memory csv gives the same balance as the file
+    #[test]
+    fn test_balance_from_reader() {
+        let mut from_file = FeeMaximizer::init_empty();
+        from_file
+            .add_balance_from_csv("test_data/multi_asset_balance.csv", true)
+            .unwrap();
+        let data = std::fs::read("test_data/multi_asset_balance.csv").unwrap();
+        let mut from_buffer = FeeMaximizer::init_empty();
+        from_buffer
+            .add_balance_from_reader(data.as_slice(), true)
+            .unwrap();
+        assert_eq!(from_file.balance(), from_buffer.balance());
+
+        let err = from_buffer
+            .add_balance_from_reader("User,Balance\nA,1\nB,-1\n".as_bytes(), true)
+            .unwrap_err();
+        assert!(matches!(err, FeeMaximizerError::NegativeBalance), "{err:?}");
+    }
+
     /// system address not allowed
     #[test]
     fn test_system_address() {
         let sys_addr = Address::from_string(format!("System")).unwrap();
         let mut req = Request::init_empty();
-        assert_eq!(
-            req.add_transaction(Transaction {
-                from: sys_addr,
-                to: Address::from_string(format!("B")).unwrap(),
-                amount: 1.0,
-                fee: 1.0,
-            }),
-            Err("cannot send to or from system address".to_string())
-        );
+        assert!(matches!(
+            req.add_transaction(Transaction::new(
+                sys_addr,
+                Address::from_string(format!("B")).unwrap(),
+                Amount::from_units(1),
+                Amount::from_units(1)
+            )),
+            Err(FeeMaximizerError::SystemAddress)
+        ));
     }
 
     #[test]
     fn test_negative_amount() {
         let mut req = Request::init_empty();
-        assert_eq!(
-            req.add_transaction(Transaction {
-                from: Address::from_string(format!("A")).unwrap(),
-                to: Address::from_string(format!("B")).unwrap(),
-                amount: -1.0,
-                fee: 0.0,
-            }),
-            Err("amount and fee must be non-negative".to_string())
-        );
+        assert!(matches!(
+            req.add_transaction(Transaction::new(
+                Address::from_string(format!("A")).unwrap(),
+                Address::from_string(format!("B")).unwrap(),
+                Amount::from_units(-1),
+                Amount::from_units(0)
+            )),
+            Err(FeeMaximizerError::NegativeAmount)
+        ));
     }
 
     #[test]
     fn test_negative_fee() {
         let mut req = Request::init_empty();
-        assert_eq!(
-            req.add_transaction(Transaction {
-                from: Address::from_string(format!("A")).unwrap(),
-                to: Address::from_string(format!("B")).unwrap(),
-                amount: 0.0,
-                fee: -1.0,
-            }),
-            Err("amount and fee must be non-negative".to_string())
-        );
+        assert!(matches!(
+            req.add_transaction(Transaction::new(
+                Address::from_string(format!("A")).unwrap(),
+                Address::from_string(format!("B")).unwrap(),
+                Amount::from_units(0),
+                Amount::from_units(-1)
+            )),
+            Err(FeeMaximizerError::NegativeAmount)
+        ));
     }
 
     /// all zeros allowed
@@ -71,12 +121,12 @@ mod illegal_inputs {
     fn test_all_zeros() {
         let mut req = Request::init_empty();
         assert!(req
-            .add_transaction(Transaction {
-                from: Address::from_string(format!("A")).unwrap(),
-                to: Address::from_string(format!("B")).unwrap(),
-                amount: 0.0,
-                fee: 0.0,
-            })
+            .add_transaction(Transaction::new(
+                Address::from_string(format!("A")).unwrap(),
+                Address::from_string(format!("B")).unwrap(),
+                Amount::from_units(0),
+                Amount::from_units(0)
+            ))
             .is_ok(),);
     }
 
@@ -84,28 +134,28 @@ mod illegal_inputs {
     fn test_empty_run() {
         let mut fm = FeeMaximizer::init_empty();
         fm.solve(100, 10, 5).unwrap();
-        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), 0.0);
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(0));
     }
 
     #[test]
     fn test_empty_balance() {
         let mut fm = FeeMaximizer::init_empty();
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: Address::from_string(format!("A")).unwrap(),
-            to: Address::from_string(format!("B")).unwrap(),
-            amount: 1.0,
-            fee: 1.0,
-        })
+        req.add_transaction(Transaction::new(
+            Address::from_string(format!("A")).unwrap(),
+            Address::from_string(format!("B")).unwrap(),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
         .unwrap();
         fm.add_request(&req);
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: Address::from_string(format!("B")).unwrap(),
-            to: Address::from_string(format!("A")).unwrap(),
-            amount: 1.0,
-            fee: 1.0,
-        })
+        req.add_transaction(Transaction::new(
+            Address::from_string(format!("B")).unwrap(),
+            Address::from_string(format!("A")).unwrap(),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
         .unwrap();
         fm.add_request(&req);
         assert!(fm.solve(100, 10, 5).is_ok());
@@ -115,14 +165,211 @@ mod illegal_inputs {
     fn test_tiny_population() {
         let mut fm = FeeMaximizer::init_empty();
         let mut req = Request::init_empty();
-        req.add_transaction(Transaction {
-            from: Address::from_string(format!("A")).unwrap(),
-            to: Address::from_string(format!("B")).unwrap(),
-            amount: 1.0,
-            fee: 1.0,
-        })
+        req.add_transaction(Transaction::new(
+            Address::from_string(format!("A")).unwrap(),
+            Address::from_string(format!("B")).unwrap(),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
         .unwrap();
         fm.add_request(&req);
         assert!(fm.solve(1, 0, 5).is_ok());
     }
 }
+
+mod request_constraints {
+    use super::*;
+    use tx_fee_maximizer::{solver_from_name, BlockCapacity, Solution, SolverConfig, SOLVER_NAMES};
+
+    fn request(from: &str, to: &str, amount: i64, fee: i64) -> Request {
+        let mut req = Request::init_empty();
+        req.add_transaction(Transaction::new(
+            Address::from_string(from.to_string()).unwrap(),
+            Address::from_string(to.to_string()).unwrap(),
+            Amount::from_units(amount),
+            Amount::from_units(fee),
+        ))
+        .unwrap();
+        req
+    }
+
+    fn weighted_request(to: &str, fee: i64, weight: u64) -> Request {
+        let mut req = Request::init_empty();
+        req.add_transaction(
+            Transaction::new(
+                Address::from_string("A".to_string()).unwrap(),
+                Address::from_string(to.to_string()).unwrap(),
+                Amount::from_units(1),
+                Amount::from_units(fee),
+            )
+            .with_weight(weight),
+        )
+        .unwrap();
+        req
+    }
+
+    /// solve with every built-in solver, and return the transactions and system balance.
+    fn solve_all(fm: &FeeMaximizer) -> Vec<(Solution, Amount)> {
+        let config = SolverConfig {
+            population_size: 256,
+            selection_size: 8,
+            num_generation: 10,
+            seed: Some(1),
+            ..SolverConfig::default()
+        };
+        SOLVER_NAMES
+            .iter()
+            .map(|name| {
+                let mut fm = fm.clone();
+                let solver = solver_from_name(name, config.clone()).unwrap();
+                let tx = fm.solve_with(solver.as_ref()).unwrap();
+                (tx, fm.get_balance(&SYSTEM_ADDRESS))
+            })
+            .collect()
+    }
+
+    fn init() -> FeeMaximizer {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+            .unwrap();
+        fm
+    }
+
+    #[test]
+    fn test_cyclic_dependency() {
+        let mut fm = init();
+        let a = fm.add_request(&request("A", "B", 1, 1));
+        let b = fm.add_request(&request("A", "C", 1, 1));
+        let c = fm.add_request(&request("A", "D", 1, 1));
+        fm.add_dependency(a, b);
+        fm.add_dependency(b, c);
+        fm.add_dependency(c, a);
+        let err = fm.solve(100, 10, 5).unwrap_err();
+        assert!(
+            matches!(&err, FeeMaximizerError::CyclicDependency(cycle) if cycle.len() == 3),
+            "{err}"
+        );
+        assert!(err.to_string().contains("cyclic dependency"), "{err}");
+    }
+
+    #[test]
+    fn test_dependency_out_of_range() {
+        let mut fm = init();
+        let a = fm.add_request(&request("A", "B", 1, 1));
+        fm.add_dependency(a, 5);
+        assert!(matches!(
+            fm.solve_with(&ExactSolver),
+            Err(FeeMaximizerError::RequestIndexOutOfRange {
+                index: 5,
+                num_requests: 1
+            })
+        ));
+    }
+
+    /// a request cannot be included without its dependency.
+    #[test]
+    fn test_dependency_inclusion() {
+        let mut fm = init();
+        let a = fm.add_request(&request("A", "B", 90, 10));
+        let b = fm.add_request(&request("A", "C", 50, 30));
+        fm.add_dependency(b, a);
+        for (tx, system_balance) in solve_all(&fm) {
+            assert_eq!(tx.len(), 1);
+            assert_eq!(system_balance, Amount::from_units(30));
+        }
+    }
+
+    /// a request must be executed after its dependency.
+    #[test]
+    fn test_dependency_order() {
+        let mut fm = init();
+        let a = fm.add_request(&request("A", "B", 10, 1));
+        let b = fm.add_request(&request("A", "C", 10, 1));
+        fm.add_dependency(a, b);
+        for (tx, system_balance) in solve_all(&fm) {
+            assert_eq!(tx.len(), 2);
+            assert_eq!(
+                tx.executed[0].transaction.to,
+                Address::from_string("C".to_string()).unwrap()
+            );
+            assert_eq!(system_balance, Amount::from_units(22));
+        }
+    }
+
+    #[test]
+    fn test_conflict() {
+        let mut fm = init();
+        let a = fm.add_request(&request("A", "B", 10, 5));
+        let b = fm.add_request(&request("A", "C", 10, 6));
+        fm.add_conflict(a, b);
+        for (tx, system_balance) in solve_all(&fm) {
+            assert_eq!(tx.len(), 1);
+            assert_eq!(system_balance, Amount::from_units(26));
+        }
+    }
+
+    /// only the most profitable transaction fits in the block.
+    #[test]
+    fn test_max_transactions() {
+        let mut fm = init();
+        fm.add_request(&request("A", "B", 1, 1));
+        fm.add_request(&request("A", "C", 1, 5));
+        fm.add_request(&request("A", "D", 1, 3));
+        fm.set_block_capacity(BlockCapacity {
+            max_transactions: Some(1),
+            max_weight: None,
+        });
+        for (tx, system_balance) in solve_all(&fm) {
+            assert_eq!(tx.len(), 1);
+            assert_eq!(system_balance, Amount::from_units(25));
+        }
+    }
+
+    /// two light transactions are better than the heaviest one.
+    #[test]
+    fn test_max_weight() {
+        let mut fm = init();
+        fm.add_request(&weighted_request("B", 5, 6));
+        fm.add_request(&weighted_request("C", 4, 5));
+        fm.add_request(&weighted_request("D", 4, 5));
+        fm.set_block_capacity(BlockCapacity {
+            max_transactions: None,
+            max_weight: Some(10),
+        });
+        for (tx, _) in solve_all(&fm) {
+            assert!(tx.transactions().map(|t| t.weight).sum::<u64>() <= 10);
+        }
+        let solution = fm.solve_with(&ExactSolver).unwrap();
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(28));
+        let usage = fm.stats().unwrap().usage;
+        assert_eq!((usage.transactions, usage.weight), (2, 10));
+        // the solution reports the same usage.
+        assert_eq!(solution.usage, usage);
+    }
+
+    /// an atomic request that does not fit is not included at all.
+    #[test]
+    fn test_atomic_over_capacity() {
+        let mut fm = init();
+        let mut req = Request::init_atomic();
+        for to in ["B", "C"] {
+            req.add_transaction(Transaction::new(
+                Address::from_string("A".to_string()).unwrap(),
+                Address::from_string(to.to_string()).unwrap(),
+                Amount::from_units(1),
+                Amount::from_units(10),
+            ))
+            .unwrap();
+        }
+        fm.add_request(&req);
+        fm.add_request(&request("A", "D", 1, 3));
+        fm.set_block_capacity(BlockCapacity {
+            max_transactions: Some(1),
+            max_weight: None,
+        });
+        for (tx, system_balance) in solve_all(&fm) {
+            assert_eq!(tx.len(), 1);
+            assert_eq!(system_balance, Amount::from_units(23));
+        }
+    }
+}
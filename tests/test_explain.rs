@@ -0,0 +1,226 @@
+mod common;
+
+use common::{fee_maximizer, request};
+use tx_fee_maximizer::*;
+
+/// Solve exactly, and explain the rejected transactions by (request, index).
+fn explain(fm: &mut FeeMaximizer) -> (Solution, Vec<(usize, usize, RejectionReason)>) {
+    let solution = fm.solve_with(&ExactSolver).unwrap();
+    let explanations = fm
+        .explain(&solution)
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.request, e.index, e.reason))
+        .collect();
+    (solution, explanations)
+}
+
+/// A pays a higher fee for request 0 than for request 1, and B can never pay request 2.
+#[test]
+fn test_infeasible_and_lower_fee() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.add_request(&request(&[("A", "B", 8, 2)]));
+    fm.add_request(&request(&[("A", "C", 5, 1)]));
+    fm.add_request(&request(&[("B", "C", 100, 1)]));
+    let (_, explanations) = explain(&mut fm);
+    assert_eq!(
+        explanations,
+        vec![
+            (
+                1,
+                0,
+                RejectionReason::LowerFee {
+                    alternative: 0,
+                    fee: Amount::from_units(1),
+                    alternative_fee: Amount::from_units(2),
+                }
+            ),
+            (
+                2,
+                0,
+                RejectionReason::Infeasible {
+                    asset: NATIVE_ASSET.clone(),
+                    shortfall: Amount::from_units(93),
+                }
+            ),
+        ]
+    );
+}
+
+/// request 2 would need the funds of both executed requests.
+#[test]
+fn test_blocked() {
+    let mut fm = fee_maximizer(&[("A", 100)]);
+    fm.add_request(&request(&[("A", "B", 40, 10)]));
+    fm.add_request(&request(&[("A", "C", 40, 10)]));
+    fm.add_request(&request(&[("A", "D", 80, 19)]));
+    let (solution, explanations) = explain(&mut fm);
+    assert_eq!(solution.total_fee, Amount::from_units(20));
+    match &explanations[..] {
+        [(2, 0, RejectionReason::Blocked { asset, by })] => {
+            assert_eq!(asset, &*NATIVE_ASSET);
+            assert_eq!(by.len(), 2);
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_constraints() {
+    let mut fm = fee_maximizer(&[("A", 100)]);
+    fm.add_request(&request(&[("A", "B", 1, 3)]));
+    fm.add_request(&request(&[("A", "C", 1, 1)]));
+    fm.add_request(&request(&[("A", "D", 1, 1)]));
+    // one transaction of an atomic request can never be paid.
+    let mut atomic = request(&[("A", "B", 1, 1), ("A", "B", 1000, 0)]);
+    atomic.set_atomic(true);
+    fm.add_request(&atomic);
+    fm.add_conflict(0, 1);
+    fm.add_dependency(2, 1);
+    let (_, explanations) = explain(&mut fm);
+    assert_eq!(
+        explanations,
+        vec![
+            (1, 0, RejectionReason::Conflict(0)),
+            (2, 0, RejectionReason::MissingDependency(1)),
+            (3, 0, RejectionReason::Atomic { index: 1 }),
+            (
+                3,
+                1,
+                RejectionReason::Infeasible {
+                    asset: NATIVE_ASSET.clone(),
+                    shortfall: Amount::from_units(1000 - 100),
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_block_full() {
+    let mut fm = fee_maximizer(&[("A", 10), ("B", 10)]);
+    fm.add_request(&request(&[("A", "C", 1, 2)]));
+    fm.add_request(&request(&[("B", "C", 1, 1)]));
+    fm.set_block_capacity(BlockCapacity {
+        max_transactions: Some(1),
+        max_weight: None,
+    });
+    let (_, explanations) = explain(&mut fm);
+    assert_eq!(explanations, vec![(1, 0, RejectionReason::BlockFull)]);
+}
+
+/// A solver that never selects anything.
+struct NoopSolver;
+
+impl Solver for NoopSolver {
+    fn name(&self) -> &str {
+        "noop"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Ok(SolverOutput {
+            selected: Vec::new(),
+            balance: problem.balance.clone(),
+            stats: SolverStats::default(),
+        })
+    }
+}
+
+#[test]
+fn test_missed() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.add_request(&request(&[("A", "B", 1, 1)]));
+    let solution = fm.solve_with(&NoopSolver).unwrap();
+    let explanations = fm.explain(&solution).unwrap();
+    assert_eq!(explanations.len(), 1);
+    assert_eq!(explanations[0].reason, RejectionReason::Missed);
+    assert_eq!(
+        explanations[0].reason.to_string(),
+        "it could be executed, but was not found by the solver"
+    );
+}
+
+/// B is funded by request 0 before spending it on request 1, but the first
+/// transaction of request 0 comes before the funding.
+#[test]
+fn test_funded_earlier_in_block() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.add_request(&request(&[("B", "E", 1, 1), ("A", "B", 6, 1)]));
+    fm.add_request(&request(&[("B", "C", 4, 2)]));
+    fm.add_request(&request(&[("B", "D", 4, 1)]));
+    let (solution, explanations) = explain(&mut fm);
+    assert_eq!(solution.total_fee, Amount::from_units(3));
+    assert_eq!(
+        explanations,
+        vec![
+            (
+                0,
+                0,
+                RejectionReason::Infeasible {
+                    asset: NATIVE_ASSET.clone(),
+                    shortfall: Amount::from_units(2),
+                }
+            ),
+            (
+                2,
+                0,
+                RejectionReason::LowerFee {
+                    alternative: 1,
+                    fee: Amount::from_units(1),
+                    alternative_fee: Amount::from_units(2),
+                }
+            ),
+        ]
+    );
+}
+
+/// A solver returning the balance before the block, which audit rejects.
+struct UnchangedBalanceSolver;
+
+impl Solver for UnchangedBalanceSolver {
+    fn name(&self) -> &str {
+        "unchanged-balance"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Ok(SolverOutput {
+            balance: problem.balance.clone(),
+            ..ExactSolver.solve(problem)?
+        })
+    }
+}
+
+/// an uncommitted solution is explained from the balance before solving.
+#[test]
+fn test_uncommitted_solution() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.set_audit(true);
+    fm.add_request(&request(&[("A", "B", 6, 2)]));
+    fm.add_request(&request(&[("A", "C", 6, 1)]));
+    let solution = fm.solve_with(&UnchangedBalanceSolver).unwrap();
+    assert!(!solution.violations.is_empty());
+    let explanations = fm.explain(&solution).unwrap();
+    assert_eq!(
+        explanations[0].reason,
+        RejectionReason::LowerFee {
+            alternative: 0,
+            fee: Amount::from_units(1),
+            alternative_fee: Amount::from_units(2),
+        }
+    );
+}
+
+#[test]
+fn test_unknown_transaction() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.add_request(&request(&[("A", "B", 1, 1)]));
+    let mut solution = fm.solve_with(&NoopSolver).unwrap();
+    solution.rejected[0].index = 1;
+    assert!(matches!(
+        fm.explain(&solution),
+        Err(FeeMaximizerError::RequestIndexOutOfRange {
+            index: 1,
+            num_requests: 1
+        })
+    ));
+}
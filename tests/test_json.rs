@@ -0,0 +1,176 @@
+mod common;
+
+use common::{address, asset};
+use std::fs::File;
+use tx_fee_maximizer::*;
+
+fn init_json() -> FeeMaximizer {
+    FeeMaximizer::init_from_json(
+        File::open("test_data/multi_asset_balance.json").unwrap(),
+        File::open("test_data/cases/multi_asset_01.json").unwrap(),
+    )
+    .unwrap()
+}
+
+/// write the problem as csv and read it back.
+fn via_csv(fm: &FeeMaximizer) -> FeeMaximizer {
+    let mut balance = Vec::new();
+    fm.write_balance_csv(&mut balance).unwrap();
+    let mut requests = Vec::new();
+    fm.write_requests_csv(&mut requests).unwrap();
+    let path = std::env::temp_dir().join(format!("balance_{}.csv", std::process::id()));
+    std::fs::write(&path, balance).unwrap();
+    let mut converted = FeeMaximizer::init_empty();
+    converted.add_balance_from_csv(&path, true).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    converted
+        .add_requests_from_reader(requests.as_slice(), true)
+        .unwrap();
+    converted
+}
+
+/// write the problem as JSON and read it back.
+fn via_json(fm: &FeeMaximizer) -> FeeMaximizer {
+    let mut balance = Vec::new();
+    fm.write_balance_json(&mut balance).unwrap();
+    let mut requests = Vec::new();
+    fm.write_requests_json(&mut requests).unwrap();
+    FeeMaximizer::init_from_json(balance.as_slice(), requests.as_slice()).unwrap()
+}
+
+fn assert_same_problem(a: &FeeMaximizer, b: &FeeMaximizer) {
+    assert_eq!(a.balance(), b.balance());
+    assert_eq!(a.requests().len(), b.requests().len());
+    for (x, y) in a.requests().iter().zip(b.requests()) {
+        assert_eq!(x.transactions(), y.transactions());
+        assert_eq!(x.is_atomic(), y.is_atomic());
+    }
+}
+
+#[test]
+fn test_load_json() {
+    let fm = init_json();
+    assert_eq!(
+        fm.get_asset_balance(&address("A"), &asset("USD")),
+        Amount::from_units(100)
+    );
+    assert_eq!(fm.get_balance(&address("A")), Amount::from_units(10));
+    assert_eq!(fm.get_balance(&address("B")), Amount::ZERO);
+
+    let requests = fm.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].id(), Some(7));
+    assert!(requests[0].is_atomic());
+    assert_eq!(requests[1].id(), None);
+    let tx = &requests[0].transactions()[1];
+    assert_eq!(tx.amount, Amount::from_units(50));
+    assert_eq!(tx.fee, "0.5".parse().unwrap());
+    assert_eq!(tx.fee_asset, asset("USD"));
+    assert_eq!(tx.weight, 3);
+    assert_eq!(requests[0].transactions()[0].fee, "2.5".parse().unwrap());
+    assert_eq!(requests[0].transactions()[0].fee_asset, *NATIVE_ASSET);
+}
+
+#[test]
+fn test_round_trip() {
+    let fm = init_json();
+    let csv = via_csv(&fm);
+    assert_same_problem(&fm, &csv);
+    // request ids are kept, and requests without id are numbered by index.
+    assert_eq!(csv.requests()[0].id(), Some(7));
+    assert_eq!(csv.requests()[1].id(), Some(1));
+    assert_same_problem(&fm, &via_json(&csv));
+
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/decimal_balance.csv", true)
+        .unwrap();
+    fm.add_requests_from_csv("test_data/cases/atomic_01.csv", true)
+        .unwrap();
+    assert_same_problem(&fm, &via_json(&fm));
+}
+
+#[test]
+fn test_same_solution() {
+    let mut fm = init_json();
+    let mut csv = via_csv(&fm);
+    let solution = fm.solve_with(&ExactSolver).unwrap();
+    let csv_solution = csv.solve_with(&ExactSolver).unwrap();
+    assert_eq!(
+        solution.into_transactions(),
+        csv_solution.into_transactions()
+    );
+    assert_eq!(fm.balance(), csv.balance());
+}
+
+#[test]
+fn test_solution_json() {
+    let mut fm = init_json();
+    let solution = fm.solve_with(&ExactSolver).unwrap();
+    let mut json = Vec::new();
+    solution.write_json(&mut json).unwrap();
+
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(value["total_fee"], "2.5");
+    assert_eq!(value["executed"][0]["request_id"], 7);
+    assert_eq!(value["executed"][0]["from"], "A");
+    assert_eq!(value["executed"][1]["asset"], "USD");
+    assert_eq!(value["rejected"][0]["request"], 1);
+    assert!(value["rejected"][0].get("request_id").is_none());
+
+    let parsed: Solution = serde_json::from_slice(&json).unwrap();
+    assert_eq!(parsed, solution);
+}
+
+#[test]
+fn test_json_amounts() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_json(r#"{"A": 0.1, "B": "12345678.12345678", "C": {"USD": 3}}"#.as_bytes())
+        .unwrap();
+    assert_eq!(fm.get_balance(&address("A")), "0.1".parse().unwrap());
+    assert_eq!(
+        fm.get_balance(&address("B")),
+        "12345678.12345678".parse().unwrap()
+    );
+    assert_eq!(
+        fm.get_asset_balance(&address("C"), &asset("USD")),
+        Amount::from_units(3)
+    );
+
+    // too many digits to be exact as a number.
+    let e = fm
+        .add_balance_from_json(r#"{"D": 12345678.12345678}"#.as_bytes())
+        .unwrap_err();
+    assert!(matches!(e, FeeMaximizerError::Json(_)), "{e}");
+    assert_eq!(fm.get_balance(&address("D")), Amount::from_minor(-1));
+}
+
+#[test]
+fn test_invalid_json() {
+    let mut fm = FeeMaximizer::init_empty();
+    let e = fm
+        .add_balance_from_json(r#"{"A": "1", "B": "-1"}"#.as_bytes())
+        .unwrap_err();
+    assert!(matches!(e, FeeMaximizerError::NegativeBalance));
+    // nothing is added.
+    assert_eq!(fm.get_balance(&address("A")), Amount::from_minor(-1));
+
+    let e = fm
+        .add_requests_from_json(
+            r#"[
+                {"transactions": [{"from": "A", "to": "B", "amount": 1, "fee": 1}]},
+                {"transactions": [{"from": "A", "to": "System", "amount": 1, "fee": 1}]}
+            ]"#
+            .as_bytes(),
+        )
+        .unwrap_err();
+    assert!(matches!(e, FeeMaximizerError::SystemAddress));
+    assert!(fm.requests().is_empty());
+
+    let e = fm
+        .add_requests_from_json(r#"[{"transactions": [{"from": "", "to": "B"}]}]"#.as_bytes())
+        .unwrap_err();
+    match e {
+        FeeMaximizerError::Json(e) => assert_eq!(e.line(), 1),
+        e => panic!("unexpected error {e}"),
+    }
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::{address, fee_maximizer, request};
+use tx_fee_maximizer::*;
+
+/// A request of (from, to, amount, fee) transactions in units, with an id.
+fn request_with_id(id: u64, txs: &[(&str, &str, i64, i64)]) -> Request {
+    let mut req = request(txs);
+    req.set_id(id);
+    req
+}
+
+fn mempool(balances: &[(&str, i64)], capacity: BlockCapacity) -> Mempool {
+    let mut fm = fee_maximizer(balances);
+    fm.set_audit(true);
+    fm.set_block_capacity(capacity);
+    Mempool::new(fm).unwrap()
+}
+
+fn ids(mempool: &Mempool) -> Vec<u64> {
+    mempool.requests().iter().map(|r| r.id().unwrap()).collect()
+}
+
+#[test]
+fn test_add_replace_remove() {
+    let mut mempool = mempool(&[("A", 10)], BlockCapacity::default());
+    mempool
+        .add(&request_with_id(1, &[("A", "B", 1, 1)]))
+        .unwrap();
+    mempool
+        .add(&request_with_id(2, &[("A", "C", 1, 1)]))
+        .unwrap();
+    assert!(matches!(
+        mempool.add(&request_with_id(1, &[])),
+        Err(FeeMaximizerError::DuplicateRequestId(1))
+    ));
+    assert!(matches!(
+        mempool.add(&Request::init_empty()),
+        Err(FeeMaximizerError::MissingRequestId)
+    ));
+
+    let replaced = mempool
+        .replace(&request_with_id(1, &[("A", "D", 2, 2)]))
+        .unwrap();
+    assert_eq!(replaced.transactions()[0].to, address("B"));
+    assert_eq!(ids(&mempool), vec![1, 2]);
+    assert_eq!(mempool.get(1).unwrap().transactions()[0].to, address("D"));
+    assert!(matches!(
+        mempool.replace(&request_with_id(3, &[])),
+        Err(FeeMaximizerError::UnknownRequestId(3))
+    ));
+
+    mempool.add_conflict(1, 2).unwrap();
+    assert!(matches!(
+        mempool.add_dependency(2, 3),
+        Err(FeeMaximizerError::UnknownRequestId(3))
+    ));
+    assert!(mempool.remove(1).is_some());
+    assert!(mempool.remove(1).is_none());
+    assert_eq!(ids(&mempool), vec![2]);
+    assert_eq!(mempool.get(2).unwrap().transactions()[0].to, address("C"));
+    // the conflict is removed with request 1.
+    let solution = mempool.solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.len(), 1);
+    assert!(mempool.is_empty());
+}
+
+/// requests of a fee maximizer keep their constraints, mapped to ids.
+#[test]
+fn test_from_fee_maximizer() {
+    let mut fm = fee_maximizer(&[("A", 10)]);
+    fm.add_request(&request_with_id(7, &[("A", "B", 1, 2)]));
+    fm.add_request(&request_with_id(8, &[("A", "C", 1, 1)]));
+    fm.add_conflict(0, 1);
+    let mut mempool = Mempool::new(fm.clone()).unwrap();
+    let solution = mempool.solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.executed[0].request_id, Some(7));
+    // request 8 conflicts with the included request 7, so it is evicted.
+    assert!(mempool.is_empty());
+
+    fm.add_request(&Request::init_empty());
+    assert!(matches!(
+        Mempool::new(fm),
+        Err(FeeMaximizerError::MissingRequestId)
+    ));
+}
+
+/// blocks of one transaction, committed one after the other.
+#[test]
+fn test_solve_commits_blocks() {
+    let one = BlockCapacity {
+        max_transactions: Some(1),
+        max_weight: None,
+    };
+    let mut mempool = mempool(&[("A", 10)], one);
+    mempool
+        .add(&request_with_id(1, &[("A", "B", 5, 1)]))
+        .unwrap();
+    mempool
+        .add(&request_with_id(2, &[("A", "C", 1, 2), ("A", "C", 100, 1)]))
+        .unwrap();
+    mempool
+        .add(&request_with_id(3, &[("B", "C", 4, 1)]))
+        .unwrap();
+    mempool.add_dependency(3, 1).unwrap();
+
+    let solution = mempool.solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.executed[0].request_id, Some(2));
+    assert_eq!(solution.executed[0].index, 0);
+    assert_eq!(solution.total_fee, Amount::from_units(2));
+    // request 2 keeps its unpayable transaction, request 3 cannot be executed
+    // before request 1 is.
+    assert_eq!(ids(&mempool), vec![1, 2, 3]);
+    assert_eq!(mempool.get(2).unwrap().transactions().len(), 1);
+    assert_eq!(
+        mempool.balance()[&(address("A"), NATIVE_ASSET.clone())],
+        Amount::from_units(7)
+    );
+
+    let solution = mempool.solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.len(), 1);
+    assert_eq!(solution.executed[0].request_id, Some(1));
+    // the dependency is settled by the block including request 1.
+    let solution = mempool.solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.executed[0].request_id, Some(3));
+    assert_eq!(ids(&mempool), vec![2]);
+    assert_eq!(
+        mempool.balance()[&(SYSTEM_ADDRESS.clone(), NATIVE_ASSET.clone())],
+        Amount::from_units(4)
+    );
+}
+
+#[test]
+fn test_warm_start() {
+    let mut mempool = mempool(&[("A", 10), ("B", 10)], BlockCapacity::default());
+    for id in 0..6 {
+        let from = if id % 2 == 0 { "A" } else { "B" };
+        mempool
+            .add(&request_with_id(id, &[(from, "C", 4, id as i64 + 1)]))
+            .unwrap();
+    }
+    assert!(mempool.warm_start().is_empty());
+    let solution = mempool.solve(20, 5, 20).unwrap();
+    assert!(!solution.is_empty());
+    let warm_start = mempool.warm_start().clone();
+    // only the pending requests keep their weights.
+    assert_eq!(warm_start.len(), mempool.len());
+    for request in mempool.requests() {
+        let id = request.id().unwrap();
+        let selection = warm_start.selection_weights(id).unwrap();
+        assert_eq!(selection.len(), request.transactions().len());
+        assert!(selection.iter().all(|w| (0.0..=1.0).contains(w)));
+        assert!((0.0..=1.0).contains(&warm_start.request_order_weight(id).unwrap()));
+    }
+    for executed in &solution.executed {
+        assert!(warm_start
+            .selection_weights(executed.request_id.unwrap())
+            .is_none());
+    }
+
+    // a new request starts without weights, and the next solve is warm-started.
+    mempool
+        .add_balance(address("A"), NATIVE_ASSET.clone(), Amount::from_units(100))
+        .unwrap();
+    mempool
+        .add(&request_with_id(6, &[("A", "C", 1, 10)]))
+        .unwrap();
+    assert!(mempool.warm_start().selection_weights(6).is_none());
+    let solution = mempool.solve(20, 5, 20).unwrap();
+    assert!(solution.executed.iter().any(|e| e.request_id == Some(6)));
+    assert!(mempool.stats().unwrap().warm_start.is_some());
+}
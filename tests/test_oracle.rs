@@ -0,0 +1,225 @@
+//! Randomized tests of the genetic solver against an exhaustive oracle on tiny instances.
+mod common;
+
+use common::address;
+use proptest::prelude::*;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::TestRunner;
+use std::collections::HashMap;
+use tx_fee_maximizer::*;
+
+const ACCOUNTS: [&str; 3] = ["A", "B", "C"];
+
+/// (from, to, amount, fee) in units.
+type Tx = (usize, usize, i64, i64);
+
+/// A tiny instance: the balance of every account in units, and (atomic, transactions) requests.
+#[derive(Clone, Debug)]
+struct Instance {
+    balances: Vec<i64>,
+    requests: Vec<(bool, Vec<Tx>)>,
+}
+
+fn instance() -> impl Strategy<Value = Instance> {
+    let tx = (0..ACCOUNTS.len(), 0..ACCOUNTS.len(), 0..=6i64, 0..=3i64);
+    let request = (any::<bool>(), prop::collection::vec(tx, 1..=2));
+    (
+        prop::collection::vec(0..=10i64, ACCOUNTS.len()),
+        prop::collection::vec(request, 1..=4),
+    )
+        .prop_map(|(balances, requests)| Instance { balances, requests })
+}
+
+impl Instance {
+    fn fee_maximizer(&self) -> FeeMaximizer {
+        let mut fm = FeeMaximizer::init_empty();
+        for (account, balance) in ACCOUNTS.iter().zip(&self.balances) {
+            fm.set_balance(
+                address(account),
+                NATIVE_ASSET.clone(),
+                Amount::from_units(*balance),
+            )
+            .unwrap();
+        }
+        for (atomic, txs) in &self.requests {
+            let mut req = Request::init_empty();
+            req.set_atomic(*atomic);
+            for (from, to, amount, fee) in txs.iter().copied() {
+                req.add_transaction(Transaction::new(
+                    address(ACCOUNTS[from]),
+                    address(ACCOUNTS[to]),
+                    Amount::from_units(amount),
+                    Amount::from_units(fee),
+                ))
+                .unwrap();
+            }
+            fm.add_request(&req);
+        }
+        fm
+    }
+
+    /// The maximum fee, trying every order of the requests
+    /// and every selection of their transactions.
+    fn oracle(&self) -> i64 {
+        let num_txs = self
+            .requests
+            .iter()
+            .map(|(_, txs)| txs.len())
+            .sum::<usize>();
+        let mut best = 0;
+        for order in permutations(self.requests.len()) {
+            'selection: for mask in 0..1u32 << num_txs {
+                let mut balances = self.balances.clone();
+                let mut fee = 0;
+                for req in order.iter().copied() {
+                    let (atomic, txs) = &self.requests[req];
+                    // the position of the first transaction of the request in the mask.
+                    let offset = self.requests[..req]
+                        .iter()
+                        .map(|(_, txs)| txs.len())
+                        .sum::<usize>();
+                    let selected = (0..txs.len())
+                        .map(|i| mask & 1 << (offset + i) != 0)
+                        .collect::<Vec<_>>();
+                    if *atomic && selected.contains(&true) && selected.contains(&false) {
+                        continue 'selection;
+                    }
+                    for ((from, to, amount, tx_fee), _) in
+                        txs.iter().copied().zip(selected).filter(|(_, s)| *s)
+                    {
+                        if balances[from] < amount + tx_fee {
+                            continue 'selection;
+                        }
+                        balances[from] -= amount + tx_fee;
+                        balances[to] += amount;
+                        fee += tx_fee;
+                    }
+                }
+                best = best.max(fee);
+            }
+        }
+        best
+    }
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for permutation in permutations(n - 1) {
+        for i in 0..=permutation.len() {
+            let mut permutation = permutation.clone();
+            permutation.insert(i, n - 1);
+            result.push(permutation);
+        }
+    }
+    result
+}
+
+/// Replay the executed transactions in order from the initial balance,
+/// returning None if a sender cannot pay its amount and fee.
+fn replay(initial: &Balance, solution: &Solution) -> Option<Balance> {
+    let mut balance = initial.clone();
+    for tx in solution.transactions() {
+        for (asset, amount) in [(&tx.asset, tx.amount), (&tx.fee_asset, tx.fee)] {
+            let from = balance.entry((tx.from.clone(), asset.clone())).or_default();
+            *from = from.checked_sub(amount).filter(|b| !b.is_negative())?;
+        }
+        for (to, asset, amount) in [
+            (&*SYSTEM_ADDRESS, &tx.fee_asset, tx.fee),
+            (&tx.to, &tx.asset, tx.amount),
+        ] {
+            let to = balance.entry((to.clone(), asset.clone())).or_default();
+            *to = to.checked_add(amount)?;
+        }
+    }
+    Some(balance)
+}
+
+/// The total held by all the addresses (including the system) in every asset.
+fn total_supply(balance: &Balance) -> HashMap<&Asset, i128> {
+    let mut total = HashMap::new();
+    for ((_, asset), amount) in balance {
+        *total.entry(asset).or_default() += amount.minor() as i128;
+    }
+    total
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// the genetic solution is feasible, conserves value and never beats the oracle.
+    #[test]
+    fn test_genetic_feasible(instance in instance(), seed in any::<u64>()) {
+        let mut fm = instance.fee_maximizer();
+        let initial = fm.balance().clone();
+        let solution = fm
+            .solve_with(&GeneticSolver::new(SolverConfig {
+                population_size: 64,
+                selection_size: 4,
+                num_generation: 5,
+                seed: Some(seed),
+                ..SolverConfig::default()
+            }))
+            .unwrap();
+
+        let replayed = replay(&initial, &solution);
+        prop_assert!(replayed.is_some(), "infeasible solution {:?}", solution);
+        let replayed = replayed.unwrap();
+        for (key, amount) in fm.balance() {
+            prop_assert_eq!(replayed.get(key).copied().unwrap_or(Amount::ZERO), *amount);
+        }
+        prop_assert_eq!(total_supply(&initial), total_supply(fm.balance()));
+        prop_assert_eq!(
+            fm.get_balance(&SYSTEM_ADDRESS) - initial[&(SYSTEM_ADDRESS.clone(), NATIVE_ASSET.clone())],
+            solution.total_fee
+        );
+        prop_assert!(solution.total_fee <= Amount::from_units(instance.oracle()));
+    }
+}
+
+/// the genetic solver finds the optimum of most tiny instances with small parameters.
+///
+/// Instances and seeds are fixed, so that the success rate is reproducible.
+#[test]
+fn test_genetic_optimal() {
+    const CASES: u64 = 64;
+    let mut runner = TestRunner::deterministic();
+    let mut optimal = 0;
+    for seed in 0..CASES {
+        let instance = instance().new_tree(&mut runner).unwrap().current();
+        let solution = instance
+            .fee_maximizer()
+            .solve_with(&GeneticSolver::new(SolverConfig {
+                population_size: 256,
+                selection_size: 16,
+                num_generation: 20,
+                seed: Some(seed),
+                ..SolverConfig::default()
+            }))
+            .unwrap();
+        if solution.total_fee == Amount::from_units(instance.oracle()) {
+            optimal += 1;
+        }
+    }
+    assert!(
+        optimal * 10 >= CASES * 9,
+        "optimal in {optimal}/{CASES} cases"
+    );
+}
+
+/// the oracle on a hand-computed instance: B can only pay its fee after receiving from A.
+#[test]
+fn test_oracle() {
+    let instance = Instance {
+        balances: vec![10, 0, 0],
+        requests: vec![
+            (false, vec![(1, 2, 2, 3)]),
+            (false, vec![(0, 1, 5, 1), (0, 2, 4, 1)]),
+        ],
+    };
+    assert_eq!(instance.oracle(), 4);
+    let solution = instance.fee_maximizer().solve_with(&ExactSolver).unwrap();
+    assert_eq!(solution.total_fee, Amount::from_units(4));
+}
@@ -0,0 +1,572 @@
+mod common;
+
+use common::{address, request};
+use std::collections::HashMap;
+use tx_fee_maximizer::*;
+
+/// A solver that never selects anything.
+struct NoopSolver;
+
+impl Solver for NoopSolver {
+    fn name(&self) -> &str {
+        "noop"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Ok(SolverOutput {
+            selected: Vec::new(),
+            balance: problem.balance.clone(),
+            stats: SolverStats {
+                solver: self.name().to_string(),
+                ..SolverStats::default()
+            },
+        })
+    }
+}
+
+fn chain_requests(fm: &mut FeeMaximizer) {
+    for (from, to, amount) in [("A", "B", 90), ("B", "A", 80), ("A", "B", 70)] {
+        fm.add_request(&request(&[(from, to, amount, 10)]));
+    }
+}
+
+#[test]
+fn test_unknown_solver() {
+    assert!(matches!(
+        solver_from_name("simulated-annealing", SolverConfig::default()),
+        Err(FeeMaximizerError::UnknownSolver(_))
+    ));
+}
+
+/// A solver that always fails.
+struct FailingSolver;
+
+impl Solver for FailingSolver {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    fn solve(&self, _: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Err(FeeMaximizerError::Solver("out of budget".to_string()))
+    }
+}
+
+#[test]
+fn test_custom_solver_error() {
+    let mut fm = FeeMaximizer::init_empty();
+    chain_requests(&mut fm);
+    let err = fm.solve_with(&FailingSolver).unwrap_err();
+    assert_eq!(err.to_string(), "out of budget");
+}
+
+/// parameters are checked even when there is nothing to solve.
+#[test]
+fn test_invalid_params_without_requests() {
+    let mut fm = FeeMaximizer::init_empty();
+    assert!(matches!(
+        fm.solve(10, 20, 5),
+        Err(FeeMaximizerError::InvalidSolverParams(_))
+    ));
+    assert!(fm.stats().is_none());
+    assert!(fm.solve(20, 10, 5).unwrap().is_empty());
+}
+
+#[test]
+fn test_solver_names() {
+    for name in SOLVER_NAMES {
+        let solver = solver_from_name(name, SolverConfig::default()).unwrap();
+        assert_eq!(solver.name(), name);
+    }
+}
+
+#[test]
+fn test_custom_solver() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    chain_requests(&mut fm);
+
+    let tx = fm.solve_with(&NoopSolver).unwrap();
+    assert!(tx.is_empty());
+    assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(20));
+    assert_eq!(fm.stats().unwrap().solver, "noop");
+}
+
+#[test]
+fn test_greedy_solver() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    chain_requests(&mut fm);
+
+    let tx = fm.solve_with(&GreedySolver).unwrap();
+    assert_eq!(tx.len(), 3);
+    assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(50));
+    assert!(!fm.stats().unwrap().optimal);
+}
+
+#[test]
+fn test_solvers_agree() {
+    let config = SolverConfig {
+        population_size: 512,
+        selection_size: 16,
+        num_generation: 20,
+        ..SolverConfig::default()
+    };
+    let mut system_balances = HashMap::new();
+    for name in SOLVER_NAMES {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+            .unwrap();
+        chain_requests(&mut fm);
+        let solver = solver_from_name(name, config.clone()).unwrap();
+        fm.solve_with(solver.as_ref()).unwrap();
+        system_balances.insert(name, fm.get_balance(&SYSTEM_ADDRESS));
+    }
+    assert!(system_balances
+        .values()
+        .all(|b| *b == Amount::from_units(50)));
+}
+
+#[test]
+fn test_seeded_reproducible() {
+    let solve = |seed: u64| {
+        let mut fm = FeeMaximizer::init_empty();
+        fm.add_balance_from_csv("test_data/initial_balance.csv", true)
+            .unwrap();
+        chain_requests(&mut fm);
+        let solver = GeneticSolver::new(SolverConfig {
+            population_size: 64,
+            selection_size: 4,
+            num_generation: 3,
+            seed: Some(seed),
+            ..SolverConfig::default()
+        });
+        let tx = fm.solve_with(&solver).unwrap();
+        let mut balance = fm.balance().iter().collect::<Vec<_>>();
+        balance.sort_by_key(|((address, asset), _)| (address.to_string(), asset.to_string()));
+        format!("{tx:?} {balance:?}")
+    };
+    assert_eq!(solve(7), solve(7));
+    assert_eq!(solve(42), solve(42));
+}
+
+/// A solver that selects the same transaction twice.
+struct DuplicateSolver;
+
+impl Solver for DuplicateSolver {
+    fn name(&self) -> &str {
+        "duplicate"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Ok(SolverOutput {
+            selected: vec![(0, 0), (0, 0)],
+            balance: problem.balance.clone(),
+            stats: SolverStats::default(),
+        })
+    }
+}
+
+#[test]
+fn test_invalid_solver_output() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    chain_requests(&mut fm);
+    let err = fm.solve_with(&DuplicateSolver).unwrap_err();
+    assert!(
+        matches!(&err, FeeMaximizerError::InvalidSolution { solver, reason }
+            if solver == "duplicate" && reason.contains("selected twice")),
+        "{err}"
+    );
+}
+
+/// A solver that selects the first transaction of every request, ignoring constraints.
+struct FirstTransactionSolver;
+
+impl Solver for FirstTransactionSolver {
+    fn name(&self) -> &str {
+        "first-transaction"
+    }
+
+    fn solve(&self, problem: &Problem) -> Result<SolverOutput, FeeMaximizerError> {
+        Ok(SolverOutput {
+            selected: (0..problem.requests.len()).map(|r| (r, 0)).collect(),
+            balance: problem.balance.clone(),
+            stats: SolverStats::default(),
+        })
+    }
+}
+
+/// the output of a custom solver is checked against the constraints before being committed.
+#[test]
+fn test_solver_output_breaking_constraints() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+
+    let mut atomic = fm.clone();
+    let mut req = request(&[("A", "B", 1, 1), ("A", "C", 1, 1)]);
+    req.set_atomic(true);
+    atomic.add_request(&req);
+    let err = atomic.solve_with(&FirstTransactionSolver).unwrap_err();
+    assert!(
+        matches!(&err, FeeMaximizerError::InvalidSolution { solver, reason }
+            if solver == "first-transaction" && reason.contains("atomic request 0")),
+        "{err}"
+    );
+    assert_eq!(atomic.balance(), fm.balance());
+
+    fm.add_request(&request(&[("A", "B", 1, 1)]));
+    fm.add_request(&request(&[("A", "C", 1, 1)]));
+    fm.add_conflict(0, 1);
+    let err = fm.solve_with(&FirstTransactionSolver).unwrap_err();
+    assert!(
+        matches!(&err, FeeMaximizerError::InvalidSolution { reason, .. }
+            if reason.contains("conflicting requests 0 and 1")),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_solution_provenance() {
+    let address_a = address("A");
+    let address_b = address("B");
+    let tx = |amount: i64, fee: i64| {
+        Transaction::new(
+            address_a.clone(),
+            address_b.clone(),
+            Amount::from_units(amount),
+            Amount::from_units(fee),
+        )
+    };
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    // the first transaction is not affordable.
+    let mut first = Request::init_empty();
+    first.set_id(1000);
+    first.add_transaction(tx(200, 1)).unwrap();
+    first.add_transaction(tx(10, 2)).unwrap();
+    fm.add_request(&first);
+    // not affordable at all.
+    let mut second = Request::init_empty();
+    second.add_transaction(tx(500, 100)).unwrap();
+    fm.add_request(&second);
+
+    let solution = fm.solve_with(&ExactSolver).unwrap();
+    assert_eq!(
+        solution.executed,
+        vec![ExecutedTransaction {
+            request: 0,
+            request_id: Some(1000),
+            index: 1,
+            transaction: tx(10, 2),
+        }]
+    );
+    assert_eq!(
+        solution.rejected,
+        vec![
+            RejectedTransaction {
+                request: 0,
+                request_id: Some(1000),
+                index: 0,
+            },
+            RejectedTransaction {
+                request: 1,
+                request_id: None,
+                index: 0,
+            },
+        ]
+    );
+    assert_eq!(solution.rejected_requests, vec![1]);
+    assert_eq!(solution.total_fee, Amount::from_units(2));
+}
+
+/// solve the chained requests with the genetic solver.
+fn solve_chain(config: SolverConfig) -> Result<(SolverStats, Amount), FeeMaximizerError> {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    chain_requests(&mut fm);
+    fm.solve_with(&GeneticSolver::new(config))?;
+    Ok((fm.stats().unwrap().clone(), fm.get_balance(&SYSTEM_ADDRESS)))
+}
+
+#[test]
+fn test_all_generations() {
+    let (stats, _) = solve_chain(SolverConfig {
+        population_size: 64,
+        selection_size: 4,
+        num_generation: 5,
+        seed: Some(1),
+        ..SolverConfig::default()
+    })
+    .unwrap();
+    assert_eq!(stats.stop_reason, StopReason::Completed);
+    assert_eq!(stats.evaluations, 64 * 5);
+}
+
+#[test]
+fn test_patience() {
+    let (stats, system_balance) = solve_chain(SolverConfig {
+        population_size: 256,
+        selection_size: 8,
+        num_generation: 10_000,
+        seed: Some(1),
+        patience: Some(3),
+        ..SolverConfig::default()
+    })
+    .unwrap();
+    assert_eq!(stats.stop_reason, StopReason::Converged);
+    assert!(stats.evaluations < 256 * 10_000);
+    assert_eq!(stats.evaluations % 256, 0);
+    // the best solution over all generations is kept.
+    assert_eq!(system_balance, Amount::from_units(50));
+
+    let e = solve_chain(SolverConfig {
+        patience: Some(0),
+        ..SolverConfig::default()
+    })
+    .unwrap_err();
+    assert!(matches!(e, FeeMaximizerError::InvalidSolverParams(_)));
+}
+
+#[test]
+fn test_time_budget() {
+    let (stats, system_balance) = solve_chain(SolverConfig {
+        population_size: 256,
+        selection_size: 8,
+        num_generation: usize::MAX,
+        seed: Some(1),
+        time_budget: Some(std::time::Duration::from_millis(200)),
+        ..SolverConfig::default()
+    })
+    .unwrap();
+    assert_eq!(stats.stop_reason, StopReason::TimeBudget);
+    assert!(stats.elapsed < std::time::Duration::from_secs(5));
+    assert!(stats.evaluations >= 256);
+    assert!(system_balance >= Amount::ZERO);
+}
+
+#[test]
+fn test_progress_and_cancellation() {
+    let token = CancellationToken::new();
+    let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let progress = {
+        let token = token.clone();
+        let reports = reports.clone();
+        ProgressCallback::new(move |progress: &Progress| {
+            reports.lock().unwrap().push(progress.clone());
+            if progress.generation == 4 {
+                token.cancel();
+            }
+        })
+    };
+    let (stats, system_balance) = solve_chain(SolverConfig {
+        population_size: 64,
+        selection_size: 4,
+        num_generation: 1000,
+        seed: Some(1),
+        progress: Some(progress),
+        cancellation: Some(token),
+        ..SolverConfig::default()
+    })
+    .unwrap();
+    assert_eq!(stats.stop_reason, StopReason::Cancelled);
+    assert_eq!(stats.evaluations, 64 * 5);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 5);
+    for (generation, report) in reports.iter().enumerate() {
+        assert_eq!(report.generation, generation);
+        assert!((0.0..=1.0).contains(&report.feasible_fraction));
+        assert!(report.mean_fee <= report.best_fee);
+    }
+    assert!(reports.windows(2).all(|w| w[0].best_fee <= w[1].best_fee));
+    // fees do not include the initial system balance of `rich_a_poor_bcd.csv`.
+    assert_eq!(reports[4].best_fee + Amount::from_units(20), system_balance);
+}
+
+#[test]
+fn test_cancelled_before_start() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let (stats, system_balance) = solve_chain(SolverConfig {
+        cancellation: Some(token),
+        ..SolverConfig::default()
+    })
+    .unwrap();
+    assert_eq!(stats.stop_reason, StopReason::Cancelled);
+    assert_eq!(stats.evaluations, 0);
+    assert_eq!(system_balance, Amount::from_units(20));
+}
+
+/// more generations never give a worse solution for the same seed.
+#[test]
+fn test_elitism() {
+    let mut previous = Amount::ZERO;
+    for num_generation in 1..=8 {
+        let (_, system_balance) = solve_chain(SolverConfig {
+            population_size: 2,
+            selection_size: 1,
+            num_generation,
+            seed: Some(3),
+            ..SolverConfig::default()
+        })
+        .unwrap();
+        assert!(system_balance >= previous, "{num_generation} generations");
+        previous = system_balance;
+    }
+}
+
+/// a selection size of 0 still keeps the best solution, like a selection size of 1.
+#[test]
+fn test_empty_selection() {
+    let config = |selection_size| SolverConfig {
+        population_size: 16,
+        selection_size,
+        num_generation: 4,
+        seed: Some(5),
+        ..SolverConfig::default()
+    };
+    let (_, system_balance) = solve_chain(config(0)).unwrap();
+    assert!(system_balance > Amount::from_units(20));
+    assert_eq!(system_balance, solve_chain(config(1)).unwrap().1);
+}
+
+/// a generation in which every agent is infeasible does not lose the balance.
+#[test]
+fn test_infeasible_generation() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    let mut req = Request::init_empty();
+    req.add_transaction(Transaction::new(
+        address("B"),
+        address("C"),
+        Amount::from_units(1),
+        Amount::from_units(1),
+    ))
+    .unwrap();
+    fm.add_request(&req);
+    for seed in 0..20 {
+        let mut fm = fm.clone();
+        let solution = fm
+            .solve_with(&GeneticSolver::new(SolverConfig {
+                population_size: 2,
+                selection_size: 1,
+                num_generation: 5,
+                seed: Some(seed),
+                ..SolverConfig::default()
+            }))
+            .unwrap();
+        assert!(solution.is_empty());
+        assert_eq!(fm.get_balance(&SYSTEM_ADDRESS), Amount::from_units(20));
+    }
+}
+
+#[test]
+fn test_selection_curve() {
+    let parse = |s: &str| s.parse::<SelectionCurve>().map(|c| format!("{c:?}"));
+    assert_eq!(parse("linear").unwrap(), "Linear");
+    assert_eq!(parse("sigmoid").unwrap(), "Sigmoid(10.0)");
+    assert_eq!(parse("power:3").unwrap(), "Power(3.0)");
+    assert!(parse("cubic").is_err());
+    assert!(parse("power:x").is_err());
+    assert!(parse("linear:2").is_err());
+
+    for curve in [
+        SelectionCurve::Linear,
+        SelectionCurve::Sigmoid(8.0),
+        SelectionCurve::Power(2.0),
+    ] {
+        assert!(curve.apply(0.0).abs() < 1e-12, "{curve:?}");
+        assert!((curve.apply(1.0) - 1.0).abs() < 1e-12, "{curve:?}");
+        assert!(curve.apply(0.25) < curve.apply(0.75), "{curve:?}");
+    }
+    assert_eq!(SelectionCurve::custom(|x| 2.0 * x).apply(0.75), 1.0);
+}
+
+#[test]
+fn test_genetic_config() {
+    let solve = |genetic: GeneticConfig| {
+        solve_chain(SolverConfig {
+            population_size: 256,
+            selection_size: 8,
+            num_generation: 10,
+            seed: Some(1),
+            genetic,
+            ..SolverConfig::default()
+        })
+    };
+    for curve in ["sigmoid:4", "power:2"] {
+        let (_, system_balance) = solve(GeneticConfig {
+            curve: curve.parse().unwrap(),
+            mutation_rate: 0.05,
+            selection_exploration: 0.1,
+            order_exploration: 0.1,
+        })
+        .unwrap();
+        assert_eq!(system_balance, Amount::from_units(50), "{curve}");
+    }
+    for invalid in [
+        GeneticConfig {
+            mutation_rate: 0.6,
+            ..GeneticConfig::default()
+        },
+        GeneticConfig {
+            order_exploration: -0.1,
+            ..GeneticConfig::default()
+        },
+        GeneticConfig {
+            curve: SelectionCurve::Power(0.0),
+            ..GeneticConfig::default()
+        },
+    ] {
+        let e = solve(invalid).unwrap_err();
+        assert!(
+            matches!(e, FeeMaximizerError::InvalidSolverParams(_)),
+            "{e}"
+        );
+    }
+}
+
+/// after a generation without any feasible agent, the learned weights are all 0.
+#[test]
+fn test_zero_weights() {
+    let mut fm = FeeMaximizer::init_empty();
+    fm.add_balance_from_csv("test_data/rich_a_poor_bcd.csv", true)
+        .unwrap();
+    for to in ["A", "C", "D"] {
+        let mut req = Request::init_empty();
+        req.add_transaction(Transaction::new(
+            address("B"),
+            address(to),
+            Amount::from_units(1),
+            Amount::from_units(1),
+        ))
+        .unwrap();
+        fm.add_request(&req);
+    }
+    for mutation_rate in [0.0, 0.1] {
+        for seed in 0..20 {
+            let solution = fm
+                .clone()
+                .solve_with(&GeneticSolver::new(SolverConfig {
+                    population_size: 2,
+                    selection_size: 1,
+                    num_generation: 5,
+                    seed: Some(seed),
+                    genetic: GeneticConfig {
+                        mutation_rate,
+                        ..GeneticConfig::default()
+                    },
+                    ..SolverConfig::default()
+                }))
+                .unwrap();
+            assert!(solution.is_empty());
+        }
+    }
+}
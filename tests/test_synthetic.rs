@@ -0,0 +1,73 @@
+use tx_fee_maximizer::*;
+
+fn requests_json(fm: &FeeMaximizer) -> String {
+    let mut json = Vec::new();
+    fm.write_requests_json(&mut json).unwrap();
+    String::from_utf8(json).unwrap()
+}
+
+/// the same parameters and seed give the same mempool.
+#[test]
+fn test_reproducible() {
+    let generator = MempoolGenerator {
+        num_accounts: 20,
+        num_requests: 50,
+        max_transactions_per_request: 3,
+        chain_depth: 4,
+        ..MempoolGenerator::default()
+    };
+    let fm = generator.generate(1);
+    assert_eq!(requests_json(&fm), requests_json(&generator.generate(1)));
+    assert_ne!(requests_json(&fm), requests_json(&generator.generate(2)));
+    assert_eq!(fm.balance(), generator.generate(1).balance());
+}
+
+#[test]
+fn test_shape() {
+    let generator = MempoolGenerator {
+        num_accounts: 10,
+        num_requests: 40,
+        max_transactions_per_request: 2,
+        balance: BalanceDistribution::Pareto {
+            min: Amount::from_units(1),
+            shape: 1.5,
+        },
+        chain_depth: 3,
+        competition: 2.0,
+    };
+    let mut fm = generator.generate(3);
+    assert_eq!(fm.requests().len(), 40);
+    assert!(fm
+        .requests()
+        .iter()
+        .all(|r| (1..=2).contains(&r.transactions().len())));
+    // 10 accounts and the system.
+    assert_eq!(fm.balance().len(), 11);
+    assert!(fm.balance().values().all(|b| *b >= Amount::ZERO));
+    // every dependency follows a chain of at most 3 requests.
+    let dependencies = fm.constraints().dependencies().to_vec();
+    assert!(!dependencies.is_empty());
+    for (request, depends_on) in &dependencies {
+        assert_eq!(*request, depends_on + 1);
+        assert_eq!(
+            fm.requests()[*request].transactions()[0].from,
+            fm.requests()[*depends_on].transactions()[0].to
+        );
+    }
+    let mut depth = [1; 40];
+    for (request, depends_on) in &dependencies {
+        depth[*request] = depth[*depends_on] + 1;
+    }
+    assert!(depth.iter().all(|d| *d <= 3));
+    fm.solve_with(&GreedySolver).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "balances cannot be negative")]
+fn test_negative_uniform_max() {
+    MempoolGenerator {
+        balance: BalanceDistribution::Uniform(Amount::from_units(-1)),
+        ..MempoolGenerator::default()
+    }
+    .generate(1);
+}
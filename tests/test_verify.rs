@@ -0,0 +1,155 @@
+mod common;
+
+use common::{address, fee_maximizer, request};
+use tx_fee_maximizer::*;
+
+/// A sends 90 to B, B sends 80 back, then A sends 70 to B, each paying a fee of 10.
+fn chain() -> FeeMaximizer {
+    let mut fm = fee_maximizer(&[("A", 100)]);
+    for (from, to, amount) in [("A", "B", 90), ("B", "A", 80), ("A", "B", 70)] {
+        fm.add_request(&request(&[(from, to, amount, 10)]));
+    }
+    fm
+}
+
+#[test]
+fn test_verify_solution() {
+    let fm = chain();
+    let mut solved = fm.clone();
+    let solution = solved.solve_with(&GreedySolver).unwrap();
+    assert_eq!(solution.len(), 3);
+
+    let verification = fm.verify(&solution).unwrap();
+    assert_eq!(verification.total_fee, Amount::from_units(30));
+    assert_eq!(verification.usage.transactions, 3);
+    for (key, amount) in solved.balance() {
+        assert_eq!(verification.balance.get(key), Some(amount));
+    }
+    assert_eq!(
+        verify(fm.balance(), fm.requests(), &solution).unwrap(),
+        verification
+    );
+    // a JSON round trip keeps the solution valid.
+    let mut json = Vec::new();
+    solution.write_json(&mut json).unwrap();
+    let read = Solution::read_json(json.as_slice()).unwrap();
+    assert_eq!(fm.verify(&read).unwrap(), verification);
+}
+
+/// the first transaction that cannot be paid is reported with its shortfall.
+#[test]
+fn test_insufficient_balance() {
+    let fm = chain();
+    let mut solution = fm.clone().solve_with(&GreedySolver).unwrap();
+    solution.executed.reverse();
+    match fm.verify(&solution) {
+        Err(FeeMaximizerError::InsufficientBalance {
+            position,
+            address: from,
+            asset,
+            shortfall,
+        }) => {
+            assert_eq!(position, 1);
+            assert_eq!(from, address("B"));
+            assert_eq!(asset, *NATIVE_ASSET);
+            assert_eq!(shortfall, Amount::from_units(20));
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_unknown_transaction() {
+    let fm = chain();
+    let solution = fm.clone().solve_with(&GreedySolver).unwrap();
+
+    let mut forged = solution.clone();
+    forged.executed[0].transaction.amount = Amount::from_units(1);
+    assert!(matches!(
+        fm.verify(&forged),
+        Err(FeeMaximizerError::UnknownTransaction { position: 0, .. })
+    ));
+
+    let mut forged = solution.clone();
+    forged.executed[2].request = 3;
+    assert!(matches!(
+        fm.verify(&forged),
+        Err(FeeMaximizerError::UnknownTransaction { position: 2, .. })
+    ));
+
+    let mut forged = solution;
+    forged.executed.push(forged.executed[0].clone());
+    assert!(matches!(
+        fm.verify(&forged),
+        Err(FeeMaximizerError::UnknownTransaction { position: 3, .. })
+    ));
+}
+
+#[test]
+fn test_fee_mismatch() {
+    let fm = chain();
+    let mut solution = fm.clone().solve_with(&GreedySolver).unwrap();
+    solution.total_fee = Amount::from_units(40);
+    match fm.verify(&solution) {
+        Err(FeeMaximizerError::FeeMismatch { claimed, actual }) => {
+            assert_eq!(claimed, Amount::from_units(40));
+            assert_eq!(actual, Amount::from_units(30));
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+/// constraints and capacity are only checked by the fee maximizer, which knows them.
+#[test]
+fn test_constraint_violated() {
+    let mut fm = chain();
+    let solution = fm.clone().solve_with(&GreedySolver).unwrap();
+    fm.add_conflict(0, 2);
+    assert!(matches!(
+        fm.verify(&solution),
+        Err(FeeMaximizerError::ConstraintViolated(_))
+    ));
+    assert!(verify(fm.balance(), fm.requests(), &solution).is_ok());
+
+    let mut fm = chain();
+    fm.add_dependency(0, 2);
+    assert!(matches!(
+        fm.verify(&solution),
+        Err(FeeMaximizerError::ConstraintViolated(_))
+    ));
+
+    let mut fm = chain();
+    fm.set_block_capacity(BlockCapacity {
+        max_transactions: Some(2),
+        max_weight: None,
+    });
+    assert!(matches!(
+        fm.verify(&solution),
+        Err(FeeMaximizerError::ConstraintViolated(_))
+    ));
+
+    // an atomic request cannot be partially executed.
+    let mut fm = chain();
+    let mut req = Request::init_atomic();
+    for amount in [1, 1000] {
+        req.add_transaction(Transaction::new(
+            address("A"),
+            address("B"),
+            Amount::from_units(amount),
+            Amount::ZERO,
+        ))
+        .unwrap();
+    }
+    fm.add_request(&req);
+    let mut solution = Solution::default();
+    solution.executed.push(ExecutedTransaction {
+        request: 3,
+        request_id: None,
+        index: 0,
+        transaction: req.transactions()[0].clone(),
+    });
+    assert!(matches!(
+        fm.verify(&solution),
+        Err(FeeMaximizerError::ConstraintViolated(_))
+    ));
+}